@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use colored::Colorize;
+
+use crate::publib::{self, alerts::Alert};
+
+const TARGET: &'static str = "commands::alerts";
+pub struct AlertsManager {
+    pub alerts: Arc<publib::AlertsClient>,
+}
+
+impl AlertsManager {
+    /// Runs `job` on a blocking thread, since the alert API is queried
+    /// with blocking requests.
+    pub async fn run<T, F>(&self, job: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&publib::AlertsClient) -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+    {
+        let alerts = self.alerts.clone();
+        tauri::async_runtime::spawn_blocking(move || job(&alerts).map_err(|err| err.to_string()))
+            .await
+            .map_err(|err| err.to_string())?
+    }
+}
+
+#[tauri::command]
+pub async fn alerts_get_base_url(
+    manager: tauri::State<'_, AlertsManager>,
+) -> Result<String, String> {
+    Ok(manager.alerts.base_url())
+}
+
+#[tauri::command]
+pub async fn alerts_set_base_url(
+    manager: tauri::State<'_, AlertsManager>,
+    base_url: String,
+) -> Result<(), String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => set base URL to {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Alerts".bright_magenta(),
+        base_url.green()
+    );
+    manager
+        .run(move |alerts| {
+            alerts.set_base_url(&base_url);
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn alerts_get_alerts(
+    manager: tauri::State<'_, AlertsManager>,
+    language_code: String,
+    refresh: Option<bool>,
+) -> Result<Vec<Alert>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get alerts in {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Alerts".bright_magenta(),
+        language_code.yellow()
+    );
+    manager
+        .run(move |alerts| alerts.get_alerts(&language_code, refresh.unwrap_or(false)))
+        .await
+}
+
+#[tauri::command]
+pub async fn alerts_set_read(
+    manager: tauri::State<'_, AlertsManager>,
+    guid: String,
+    read: bool,
+) -> Result<(), String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => mark {} as {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Alerts".bright_magenta(),
+        guid.yellow(),
+        if read { "read" } else { "unread" }
+    );
+    manager
+        .run(move |alerts| alerts.set_read(&guid, read))
+        .await
+}
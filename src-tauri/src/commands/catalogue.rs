@@ -1,17 +1,41 @@
+use std::sync::Arc;
+
+use chrono::NaiveDate;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use tauri::async_runtime::Mutex;
 
 use crate::publib::{
     self,
-    catalog::{CollectionImage, CollectionPublication},
-    publication::ContentTables,
+    catalog::{
+        CollectionBilingualDocument, CollectionDatedText, CollectionDocumentLocation,
+        CollectionDocumentTranslation, CollectionExtract, CollectionImage, CollectionMeetingWeek,
+        CollectionPeriodicalGroup, CollectionPublication, CollectionRelatedDocument,
+        CollectionTopic,
+    },
+    publication::{
+        ContentTables, DocumentMedia, ExtractVideoSegment, PublicationTopic, VideoSegment,
+    },
     tables::{Document, PublicationViewItem, PublicationViewItemDocument},
 };
 
 const TARGET: &'static str = "commands::catalog";
 pub struct CatalogManager {
-    pub catalog: Mutex<publib::Catalog>,
+    pub catalog: Arc<publib::Catalog>,
+}
+
+impl CatalogManager {
+    /// Runs `job` on a blocking thread, so SQLite queries and content
+    /// decryption don't stall the async runtime or each other.
+    pub async fn run<T, F>(&self, job: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&publib::Catalog) -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+    {
+        let catalog = self.catalog.clone();
+        tauri::async_runtime::spawn_blocking(move || job(&catalog).map_err(|err| err.to_string()))
+            .await
+            .map_err(|err| err.to_string())?
+    }
 }
 
 #[tauri::command]
@@ -25,11 +49,12 @@ pub async fn catalog_install_jwpub_from_archive(
         "COMMAND_REQUEST".bright_green(),
         "Catalog".bright_magenta(),
     );
-    let mut catalog = manager.catalog.lock().await;
-    catalog
-        .install_jwpub_from_archive(file)
-        .map_err(|err| err.to_string())?;
-    Ok(())
+    manager
+        .run(move |catalog| {
+            catalog.install_jwpub_from_archive(file)?;
+            Ok(())
+        })
+        .await
 }
 
 #[tauri::command]
@@ -44,11 +69,12 @@ pub async fn catalog_install_jwpub_file(
         "Catalog".bright_magenta(),
         file_path.green()
     );
-    let mut catalog = manager.catalog.lock().await;
-    catalog
-        .install_jwpub_file(file_path)
-        .map_err(|err| err.to_string())?;
-    Ok(())
+    manager
+        .run(move |catalog| {
+            catalog.install_jwpub_file(file_path)?;
+            Ok(())
+        })
+        .await
 }
 
 #[tauri::command]
@@ -63,10 +89,9 @@ pub async fn catalog_get_count_from_type(
         "Catalog".bright_magenta(),
         publication_type.green()
     );
-    let catalog = manager.catalog.lock().await;
-    catalog
-        .get_count_of_type(&publication_type)
-        .map_err(|err| err.to_string())
+    manager
+        .run(move |catalog| catalog.get_count_of_type(&publication_type))
+        .await
 }
 
 // Can change for language support
@@ -82,10 +107,26 @@ pub async fn catalog_get_list_from_type(
         "Catalog".bright_magenta(),
         publication_type.green()
     );
-    let catalog = manager.catalog.lock().await;
-    catalog
-        .get_list_from_type(&publication_type)
-        .map_err(|err| err.to_string())
+    manager
+        .run(move |catalog| catalog.get_list_from_type(&publication_type))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_periodicals_from_type(
+    manager: tauri::State<'_, CatalogManager>,
+    publication_type: String,
+) -> Result<Vec<CollectionPeriodicalGroup>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get periodicals grouped by issue from type {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog".bright_magenta(),
+        publication_type.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_periodicals_from_type(&publication_type))
+        .await
 }
 
 #[derive(Serialize, Deserialize)]
@@ -106,10 +147,9 @@ pub async fn catalog_open_connection(
         "Catalog".bright_magenta(),
         filename_symbol.green()
     );
-    let mut catalog = manager.catalog.lock().await;
-    catalog
-        .open_publication_connection(filename_symbol)
-        .map_err(|err| err.to_string())
+    manager
+        .run(move |catalog| catalog.open_publication_connection(filename_symbol))
+        .await
 }
 
 #[tauri::command]
@@ -124,22 +164,16 @@ pub async fn catalog_get_publication_view_from(
         "Catalog -> Publication".bright_magenta(),
         filename_symbol.green()
     );
-    let mut catalog = manager.catalog.lock().await;
-    catalog
-        .open_publication_connection(filename_symbol)
-        .map_err(|err| err.to_string())?;
-    if let Some(publication) = catalog.get_current_publication() {
-        return Ok(PublicationViewResponse {
-            publication_view_items: publication
-                .get_view_items()
-                .map_err(|err| err.to_string())?,
-            publication_view_items_documents: publication
-                .get_view_items_documents()
-                .map_err(|err| err.to_string())?,
-        });
-    }
+    manager
+        .run(move |catalog| {
+            let publication = catalog.get_publication(&filename_symbol)?;
 
-    Err("This publication doesn't exist or wasn't open.".to_owned())
+            Ok(PublicationViewResponse {
+                publication_view_items: publication.get_view_items()?,
+                publication_view_items_documents: publication.get_view_items_documents()?,
+            })
+        })
+        .await
 }
 
 #[tauri::command]
@@ -157,10 +191,9 @@ pub async fn catalog_get_images_of_type(
         image_type.green()
     );
 
-    let catalog = manager.catalog.lock().await;
-    catalog
-        .get_images_of_type(&image_type, publication_id)
-        .map_err(|err| err.to_string())
+    manager
+        .run(move |catalog| catalog.get_images_of_type(&image_type, publication_id))
+        .await
 }
 
 #[tauri::command]
@@ -173,12 +206,15 @@ pub async fn catalog_get_documents(
         "COMMAND_REQUEST".bright_green(),
         "Catalog -> Publication".bright_magenta(),
     );
-    let mut catalog = manager.catalog.lock().await;
-    if let Some(publication) = catalog.get_current_publication() {
-        return Ok(publication.get_documents().map_err(|err| err.to_string())?);
-    }
+    manager
+        .run(move |catalog| {
+            if let Some(publication) = catalog.get_current_publication() {
+                return Ok(publication.get_documents()?);
+            }
 
-    Err("There aren't a publication open.".to_owned())
+            Err("There aren't a publication open.".into())
+        })
+        .await
 }
 
 #[tauri::command]
@@ -193,14 +229,15 @@ pub async fn catalog_get_document_by_id(
         "Catalog -> Publication".bright_magenta(),
         document_id.to_string().yellow()
     );
-    let mut catalog = manager.catalog.lock().await;
-    if let Some(publication) = catalog.get_current_publication() {
-        return Ok(publication
-            .get_document_by_id(document_id)
-            .map_err(|err| err.to_string())?);
-    }
+    manager
+        .run(move |catalog| {
+            if let Some(publication) = catalog.get_current_publication() {
+                return Ok(publication.get_document_by_id(document_id)?);
+            }
 
-    Err("There aren't a publication open.".to_owned())
+            Err("There aren't a publication open.".into())
+        })
+        .await
 }
 
 #[tauri::command]
@@ -215,20 +252,18 @@ pub async fn catalog_check_document_exists(
         "Catalog -> Publication".bright_magenta(),
         document_id.to_string().yellow()
     );
-    let mut catalog = manager.catalog.lock().await;
-    if let Some(publication) = catalog.get_current_publication() {
-        return Ok(
-            match publication
-                .get_document_by_id(document_id)
-                .map_err(|err| err.to_string())?
-            {
-                Some(_document) => true,
-                None => false,
-            },
-        );
-    }
+    manager
+        .run(move |catalog| {
+            if let Some(publication) = catalog.get_current_publication() {
+                return Ok(match publication.get_document_by_id(document_id)? {
+                    Some(_document) => true,
+                    None => false,
+                });
+            }
 
-    Err("There aren't a publication open.".to_owned())
+            Err("There aren't a publication open.".into())
+        })
+        .await
 }
 
 #[tauri::command]
@@ -243,12 +278,374 @@ pub async fn catalog_get_document_content(
         "Catalog -> Publication".bright_magenta(),
         document_id.to_string().yellow()
     );
-    let mut catalog = manager.catalog.lock().await;
-    if let Some(publication) = catalog.get_current_publication() {
-        return Ok(publication
-            .get_content_text_from(ContentTables::Document, document_id)
-            .map_err(|err| err.to_string())?);
-    }
+    manager
+        .run(move |catalog| {
+            if let Some(publication) = catalog.get_current_publication() {
+                return Ok(publication.get_content_text_from(ContentTables::Document, document_id)?);
+            }
+
+            Err("There aren't a publication open.".into())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_documents_from(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+) -> Result<Vec<Document>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get documents from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_publication(&filename_symbol)?.get_documents())
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_document_by_id_from(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+) -> Result<Option<Document>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get document by ID {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| {
+            catalog
+                .get_publication(&filename_symbol)?
+                .get_document_by_id(document_id)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_check_document_exists_from(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+) -> Result<bool, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => check document ID {} exists on {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| {
+            Ok(catalog
+                .get_publication(&filename_symbol)?
+                .get_document_by_id(document_id)?
+                .is_some())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_document_content_from(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+) -> Result<Option<String>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get document content decrypted from {} on {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| {
+            catalog
+                .get_publication(&filename_symbol)?
+                .get_content_text_from(ContentTables::Document, document_id)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_dated_text(
+    manager: tauri::State<'_, CatalogManager>,
+    date: NaiveDate,
+    class: i32,
+    language_index: i32,
+) -> Result<Option<CollectionDatedText>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get dated text for {} (Class {}, Language {})",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        date.to_string().green(),
+        class.to_string().yellow(),
+        language_index.to_string().yellow()
+    );
+    manager
+        .run(move |catalog| catalog.dated_text_for(date, class, language_index))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_meeting_week(
+    manager: tauri::State<'_, CatalogManager>,
+    date: NaiveDate,
+    language_index: i32,
+) -> Result<CollectionMeetingWeek, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get meeting materials for the week of {} (Language {})",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        date.to_string().green(),
+        language_index.to_string().yellow()
+    );
+    manager
+        .run(move |catalog| catalog.meeting_week_for(date, language_index))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_topics(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    query: String,
+) -> Result<Vec<PublicationTopic>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get topics matching \"{}\" from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        query.green(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_topic_index_of(&filename_symbol, &query))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_search_topics(
+    manager: tauri::State<'_, CatalogManager>,
+    query: String,
+) -> Result<Vec<CollectionTopic>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => search topics matching \"{}\"",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog".bright_magenta(),
+        query.green()
+    );
+    manager
+        .run(move |catalog| catalog.search_topics(&query))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_document_extracts(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+) -> Result<Vec<CollectionExtract>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get extracts of document {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_document_extracts_of(&filename_symbol, document_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_related_documents(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+    relationship_type: Option<i32>,
+) -> Result<Vec<CollectionRelatedDocument>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get related documents of document {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| {
+            catalog.get_related_documents_of(&filename_symbol, document_id, relationship_type)
+        })
+        .await
+}
 
-    Err("There aren't a publication open.".to_owned())
+#[tauri::command]
+pub async fn catalog_get_document_languages(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+) -> Result<Vec<CollectionDocumentLocation>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get languages of document {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_document_languages_of(&filename_symbol, document_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_switch_document_language(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+    language_index: i32,
+    paragraph_index: Option<i32>,
+) -> Result<Option<CollectionDocumentTranslation>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => switch document {} from {} to language {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green(),
+        language_index.to_string().yellow()
+    );
+    manager
+        .run(move |catalog| {
+            catalog.switch_document_language(
+                &filename_symbol,
+                document_id,
+                language_index,
+                paragraph_index,
+            )
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_bilingual_document(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+    language_index: i32,
+) -> Result<Option<CollectionBilingualDocument>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => align document {} from {} with language {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green(),
+        language_index.to_string().yellow()
+    );
+    manager
+        .run(move |catalog| {
+            catalog.get_bilingual_document(&filename_symbol, document_id, language_index)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_document_media(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: Option<i32>,
+) -> Result<Vec<DocumentMedia>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get media of document {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id
+            .map(|id| id.to_string())
+            .unwrap_or("*".to_owned())
+            .yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_document_media_of(&filename_symbol, document_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_paragraph_video_segments(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+    paragraph_index: Option<i32>,
+) -> Result<Vec<VideoSegment>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get video segments of document {} (paragraph {}) from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        document_id.to_string().yellow(),
+        paragraph_index
+            .map(|index| index.to_string())
+            .unwrap_or("*".to_owned())
+            .yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| {
+            catalog.get_paragraph_video_segments_of(&filename_symbol, document_id, paragraph_index)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_verse_video_segments(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    bible_verse_id: i32,
+) -> Result<Vec<VideoSegment>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get video segments of Bible verse {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        bible_verse_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_verse_video_segments_of(&filename_symbol, bible_verse_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn catalog_get_extract_video_segments(
+    manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    extract_id: i32,
+) -> Result<Vec<ExtractVideoSegment>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get video segments of extract {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Catalog -> Publication".bright_magenta(),
+        extract_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(move |catalog| catalog.get_extract_video_segments_of(&filename_symbol, extract_id))
+        .await
 }
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use colored::Colorize;
+
+use crate::publib::{self, download::DownloadEvent};
+
+const TARGET: &'static str = "commands::downloads";
+
+pub struct DownloadManagerState {
+    pub downloader: Arc<publib::DownloadManager>,
+}
+
+#[tauri::command]
+pub async fn downloads_get_jobs(
+    state: tauri::State<'_, DownloadManagerState>,
+) -> Result<Vec<DownloadEvent>, String> {
+    Ok(state.downloader.jobs())
+}
+
+#[tauri::command]
+pub async fn downloads_cancel(
+    state: tauri::State<'_, DownloadManagerState>,
+    job_id: publib::download::JobId,
+) -> Result<bool, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => cancel job {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Downloads".bright_magenta(),
+        job_id.to_string().yellow()
+    );
+    Ok(state.downloader.cancel(job_id))
+}
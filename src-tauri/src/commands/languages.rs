@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use colored::Colorize;
+
+use crate::publib::{
+    self,
+    languages::{InstalledLanguage, Language},
+};
+
+use super::{catalogue::CatalogManager, media::MediaCatalogManager};
+
+const TARGET: &'static str = "commands::languages";
+pub struct LanguageRegistryManager {
+    pub registry: Arc<publib::LanguageRegistry>,
+}
+
+impl LanguageRegistryManager {
+    /// Runs `job` on a blocking thread with both the registry and the local
+    /// catalog, which languages are learned from.
+    pub async fn run<T, F>(&self, catalog_manager: &CatalogManager, job: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(
+                &publib::LanguageRegistry,
+                &publib::Catalog,
+            ) -> Result<T, Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        let registry = self.registry.clone();
+        let catalog = catalog_manager.catalog.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            job(&registry, &catalog).map_err(|err| err.to_string())
+        })
+        .await
+        .map_err(|err| err.to_string())?
+    }
+}
+
+#[tauri::command]
+pub async fn languages_get_all(
+    manager: tauri::State<'_, LanguageRegistryManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+) -> Result<Vec<Language>, String> {
+    manager
+        .run(&catalog_manager, move |registry, catalog| {
+            registry.learn_from_catalog(catalog)?;
+            Ok(registry.languages())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn languages_get(
+    manager: tauri::State<'_, LanguageRegistryManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    meps_index: i32,
+) -> Result<Option<Language>, String> {
+    manager
+        .run(&catalog_manager, move |registry, catalog| {
+            if registry.get(meps_index).is_none() {
+                registry.learn_from_catalog(catalog)?;
+            }
+            Ok(registry.get(meps_index))
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn languages_get_installed(
+    manager: tauri::State<'_, LanguageRegistryManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+) -> Result<Vec<InstalledLanguage>, String> {
+    manager
+        .run(&catalog_manager, move |registry, catalog| {
+            registry.learn_from_catalog(catalog)?;
+            registry.installed_languages(catalog)
+        })
+        .await
+}
+
+/// Learns the installed languages and names them with the media catalog
+/// languages, downloaded again.
+#[tauri::command]
+pub async fn languages_refresh(
+    manager: tauri::State<'_, LanguageRegistryManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    media_manager: tauri::State<'_, MediaCatalogManager>,
+) -> Result<Vec<Language>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => refresh",
+        "COMMAND_REQUEST".bright_green(),
+        "Languages".bright_magenta(),
+    );
+    let media = media_manager.media.clone();
+    manager
+        .run(&catalog_manager, move |registry, catalog| {
+            registry.learn_from_catalog(catalog)?;
+            registry.merge_media_languages(&media.get_languages(true)?)?;
+            Ok(registry.languages())
+        })
+        .await
+}
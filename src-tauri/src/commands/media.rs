@@ -0,0 +1,267 @@
+use std::sync::Arc;
+
+use colored::Colorize;
+
+use crate::publib::{
+    self,
+    media::{MediaItem, MediaLanguage, MediaRecording},
+    media_store::{MediaStoreUsage, StoredMedia},
+};
+
+use super::catalogue::CatalogManager;
+
+const TARGET: &'static str = "commands::media";
+pub struct MediaCatalogManager {
+    pub media: Arc<publib::MediaCatalog>,
+}
+
+impl MediaCatalogManager {
+    /// Runs `job` on a blocking thread with both the media and the local
+    /// catalog, since catalog downloads and media lookups are blocking.
+    pub async fn run<T, F>(&self, catalog_manager: &CatalogManager, job: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&publib::MediaCatalog, &publib::Catalog) -> Result<T, Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        let media = self.media.clone();
+        let catalog = catalog_manager.catalog.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            job(&media, &catalog).map_err(|err| err.to_string())
+        })
+        .await
+        .map_err(|err| err.to_string())?
+    }
+}
+
+pub struct MediaStoreManager {
+    pub store: Arc<publib::MediaStore>,
+}
+
+impl MediaStoreManager {
+    /// Runs `job` on a blocking thread with both the media store and the
+    /// local catalog.
+    pub async fn run<T, F>(&self, catalog_manager: &CatalogManager, job: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&publib::MediaStore, &publib::Catalog) -> Result<T, Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        let store = self.store.clone();
+        let catalog = catalog_manager.catalog.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            job(&store, &catalog).map_err(|err| err.to_string())
+        })
+        .await
+        .map_err(|err| err.to_string())?
+    }
+}
+
+#[tauri::command]
+pub async fn media_get_languages(
+    manager: tauri::State<'_, MediaCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    refresh: Option<bool>,
+) -> Result<Vec<MediaLanguage>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get languages",
+        "COMMAND_REQUEST".bright_green(),
+        "Media".bright_magenta(),
+    );
+    manager
+        .run(&catalog_manager, move |media, _| {
+            Ok(media.get_languages(refresh.unwrap_or(false))?.to_vec())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn media_get_catalog_items(
+    manager: tauri::State<'_, MediaCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    language_code: String,
+    pub_symbol: Option<String>,
+) -> Result<Vec<MediaItem>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get catalog items of {} in {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Media".bright_magenta(),
+        pub_symbol.as_deref().unwrap_or("*").green(),
+        language_code.yellow()
+    );
+    manager
+        .run(&catalog_manager, move |media, _| {
+            media.get_media_items(&language_code, pub_symbol.as_deref())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn media_get_publication_media(
+    manager: tauri::State<'_, MediaCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+) -> Result<Vec<MediaRecording>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get media of {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Media".bright_magenta(),
+        filename_symbol.green()
+    );
+    manager
+        .run(&catalog_manager, move |media, catalog| {
+            Ok(media
+                .get_installed_publication_media(catalog, &filename_symbol)?
+                .to_vec())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn media_get_document_media(
+    manager: tauri::State<'_, MediaCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    document_id: i32,
+) -> Result<Vec<MediaRecording>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get media of document {} from {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Media".bright_magenta(),
+        document_id.to_string().yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(&catalog_manager, move |media, catalog| {
+            Ok(media
+                .get_installed_document_media(catalog, &filename_symbol, document_id)?
+                .to_vec())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn media_store_get_media(
+    manager: tauri::State<'_, MediaStoreManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: Option<String>,
+    meps_document_id: Option<i32>,
+) -> Result<Vec<StoredMedia>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get stored media of {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Media store".bright_magenta(),
+        filename_symbol.as_deref().unwrap_or("*").green()
+    );
+    manager
+        .run(&catalog_manager, move |store, _| {
+            store.get_media(filename_symbol.as_deref(), meps_document_id)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn media_store_get_usage(
+    manager: tauri::State<'_, MediaStoreManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+) -> Result<MediaStoreUsage, String> {
+    manager
+        .run(&catalog_manager, move |store, _| store.usage())
+        .await
+}
+
+#[tauri::command]
+pub async fn media_store_set_quota(
+    manager: tauri::State<'_, MediaStoreManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    quota: Option<u64>,
+) -> Result<(), String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => set quota",
+        "COMMAND_REQUEST".bright_green(),
+        "Media store".bright_magenta(),
+    );
+    manager
+        .run(&catalog_manager, move |store, _| store.set_quota(quota))
+        .await
+}
+
+/// Downloads a recording listed by `media_get_publication_media` or
+/// `media_get_document_media`. Progress is reported through
+/// `download-job-progress` events.
+#[tauri::command]
+pub async fn media_store_download(
+    manager: tauri::State<'_, MediaStoreManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+    meps_document_id: Option<i32>,
+    recording: MediaRecording,
+    label: Option<String>,
+) -> Result<StoredMedia, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => download {} media for {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Media store".bright_magenta(),
+        label.as_deref().unwrap_or("default").yellow(),
+        filename_symbol.green()
+    );
+    manager
+        .run(&catalog_manager, move |store, catalog| {
+            store.download(
+                catalog,
+                &filename_symbol,
+                meps_document_id,
+                &recording,
+                label.as_deref(),
+                |_| {},
+            )
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn media_store_delete(
+    manager: tauri::State<'_, MediaStoreManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    id: i64,
+) -> Result<bool, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => delete media {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Media store".bright_magenta(),
+        id.to_string().yellow()
+    );
+    manager
+        .run(&catalog_manager, move |store, _| store.delete(id))
+        .await
+}
+
+#[tauri::command]
+pub async fn media_store_delete_publication_media(
+    manager: tauri::State<'_, MediaStoreManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    filename_symbol: String,
+) -> Result<usize, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => delete media of {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Media store".bright_magenta(),
+        filename_symbol.green()
+    );
+    manager
+        .run(&catalog_manager, move |store, _| {
+            store.delete_publication_media(&filename_symbol)
+        })
+        .await
+}
@@ -1,2 +1,7 @@
 pub mod catalogue;
 pub mod settings;
+pub mod remote;
+pub mod downloads;
+pub mod media;
+pub mod alerts;
+pub mod languages;
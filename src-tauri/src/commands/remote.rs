@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use colored::Colorize;
+use tauri::{Emitter, Manager};
+
+use crate::publib::{
+    self,
+    remote::{
+        PublicationUpdate, PublicationUpdateResult, RemoteFilter, RemoteLanguage, RemoteManifest,
+        RemotePublication, RemotePublicationType,
+    },
+};
+
+use super::catalogue::CatalogManager;
+
+const TARGET: &'static str = "commands::remote";
+pub struct RemoteCatalogManager {
+    pub remote: Arc<publib::RemoteCatalog>,
+}
+
+impl RemoteCatalogManager {
+    /// Runs `job` on a blocking thread with both the remote and the local
+    /// catalog, since downloads and SQLite queries are blocking.
+    pub async fn run<T, F>(&self, catalog_manager: &CatalogManager, job: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(
+                &publib::RemoteCatalog,
+                &publib::Catalog,
+            ) -> Result<T, Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        let remote = self.remote.clone();
+        let catalog = catalog_manager.catalog.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            job(&remote, &catalog).map_err(|err| err.to_string())
+        })
+        .await
+        .map_err(|err| err.to_string())?
+    }
+}
+
+#[tauri::command]
+pub async fn remote_get_base_url(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+) -> Result<String, String> {
+    Ok(manager.remote.base_url())
+}
+
+#[tauri::command]
+pub async fn remote_set_base_url(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    base_url: String,
+) -> Result<(), String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => set base URL to {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        base_url.green()
+    );
+    manager.remote.set_base_url(&base_url);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remote_set_pub_media_base_url(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    base_url: String,
+) -> Result<(), String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => set pub-media base URL to {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        base_url.green()
+    );
+    manager.remote.set_pub_media_base_url(&base_url);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remote_get_manifest(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+) -> Result<Option<RemoteManifest>, String> {
+    Ok(manager.remote.manifest())
+}
+
+#[tauri::command]
+pub async fn remote_refresh_catalog(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+) -> Result<RemoteManifest, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => refresh remote catalog",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+    );
+    manager
+        .run(&catalog_manager, move |remote, _catalog| remote.refresh())
+        .await
+}
+
+#[tauri::command]
+pub async fn remote_get_languages(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+) -> Result<Vec<RemoteLanguage>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get languages",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+    );
+    manager
+        .run(&catalog_manager, move |remote, _catalog| {
+            remote.ensure_downloaded()?;
+            remote.get_languages()
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn remote_get_publication_types(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    meps_language_id: i32,
+) -> Result<Vec<RemotePublicationType>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get publication types for language {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        meps_language_id.to_string().yellow()
+    );
+    manager
+        .run(&catalog_manager, move |remote, _catalog| {
+            remote.ensure_downloaded()?;
+            remote.get_publication_types(meps_language_id)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn remote_get_years(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    meps_language_id: i32,
+    publication_type: Option<String>,
+) -> Result<Vec<i32>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get years for language {} of type {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        meps_language_id.to_string().yellow(),
+        publication_type.as_deref().unwrap_or("*").green()
+    );
+    manager
+        .run(&catalog_manager, move |remote, _catalog| {
+            remote.ensure_downloaded()?;
+            remote.get_years(meps_language_id, publication_type.as_deref())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn remote_get_publications(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    meps_language_id: i32,
+    publication_type: Option<String>,
+    year: Option<i32>,
+) -> Result<Vec<RemotePublication>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => get publications for language {} of type {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        meps_language_id.to_string().yellow(),
+        publication_type.as_deref().unwrap_or("*").green()
+    );
+    manager
+        .run(&catalog_manager, move |remote, catalog| {
+            remote.ensure_downloaded()?;
+            remote.get_publications(
+                &RemoteFilter {
+                    meps_language_id,
+                    publication_type,
+                    year,
+                },
+                &catalog.get_publications()?,
+            )
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn remote_download_publication(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    publication_id: i32,
+    language_code: String,
+) -> Result<(), String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => download and install publication ID {} ({})",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        publication_id.to_string().yellow(),
+        language_code.green()
+    );
+    manager
+        .run(&catalog_manager, move |remote, catalog| {
+            // Emitted on every whole percent, chunks are too small to report each
+            let mut last_percent = None;
+            remote.download_publication(catalog, publication_id, &language_code, |progress| {
+                let percent = match progress.total {
+                    0 => 0,
+                    total => progress.downloaded * 100 / total,
+                };
+                if last_percent != Some(percent) {
+                    last_percent = Some(percent);
+                    let _ = app.emit("publication-download-progress", progress);
+                }
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn remote_cancel_download(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    publication_id: i32,
+) -> Result<bool, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => cancel download of publication ID {}",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        publication_id.to_string().yellow()
+    );
+    Ok(manager.remote.cancel_download(publication_id))
+}
+
+#[tauri::command]
+pub async fn remote_check_updates(
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    refresh: Option<bool>,
+) -> Result<Vec<PublicationUpdate>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => check updates for installed publications",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+    );
+    manager
+        .run(&catalog_manager, move |remote, catalog| {
+            if refresh.unwrap_or(false) {
+                remote.refresh()?;
+            } else {
+                remote.ensure_downloaded()?;
+            }
+            remote.get_available_updates(&catalog.get_publications()?)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn remote_apply_updates(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, RemoteCatalogManager>,
+    catalog_manager: tauri::State<'_, CatalogManager>,
+    updates: Vec<PublicationUpdate>,
+) -> Result<Vec<PublicationUpdateResult>, String> {
+    debug!(
+        target: TARGET,
+        "{}: {} => apply {} publication updates",
+        "COMMAND_REQUEST".bright_green(),
+        "Remote".bright_magenta(),
+        updates.len().to_string().yellow()
+    );
+    manager
+        .run(&catalog_manager, move |remote, catalog| {
+            let mut last_progress = None;
+            Ok(remote.apply_updates(catalog, &updates, |progress| {
+                let percent = match progress.total {
+                    0 => 0,
+                    total => progress.downloaded * 100 / total,
+                };
+                if last_progress != Some((progress.publication_id, percent)) {
+                    last_progress = Some((progress.publication_id, percent));
+                    let _ = app.emit("publication-download-progress", progress);
+                }
+            }))
+        })
+        .await
+}
+
+/// Checks installed publications against the cached remote catalog in
+/// background and emits `publication-updates-available` when some are
+/// stale. The catalog is only downloaded when none was cached yet, newer
+/// builds are fetched by `remote_check_updates` with `refresh`.
+pub fn spawn_update_check(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let manager = app.state::<RemoteCatalogManager>();
+        let catalog_manager = app.state::<CatalogManager>();
+
+        let updates = manager
+            .run(&catalog_manager, |remote, catalog| {
+                let installed = catalog.get_publications()?;
+                if installed.is_empty() {
+                    return Ok(Vec::new());
+                }
+                remote.ensure_downloaded()?;
+                remote.get_available_updates(&installed)
+            })
+            .await;
+
+        match updates {
+            Ok(updates) if !updates.is_empty() => {
+                info!(target: TARGET, "{} publication updates available.", updates.len().to_string().yellow());
+                let _ = app.emit("publication-updates-available", updates);
+            }
+            Ok(_) => {}
+            Err(err) => warn!(target: TARGET, "Couldn't check publication updates: {}", err),
+        }
+    });
+}
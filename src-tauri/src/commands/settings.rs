@@ -1,78 +1,91 @@
-use std::{fs, io::Write};
+use std::{collections::HashMap, fs};
 
-use futures_util::StreamExt;
 use tauri::{Emitter, Manager};
 
-use crate::handlers::www::is_base_assets_present;
+use crate::handlers::www::{is_base_assets_present, BaseAssetManifest, BaseAssetStatus};
+use crate::publib::download::{
+    partial_path_of, DownloadKind, DownloadRequest, DownloadState, ExpectedFile, JobId,
+};
 
-#[tauri::command]
-pub async fn settings_download_base_assets(app: tauri::AppHandle) -> Result<(), String> {
-    let font1_response = reqwest::get("https://assetsnffrgf-a.akamaihd.net/assets/ct/1add6d1d93/fonts/jw-icons-external-1970474.woff").await.map_err(|e| e.to_string())?;
-    let font2_response = reqwest::get("https://assetsnffrgf-a.akamaihd.net/assets/ct/1add6d1d93/fonts/jw-icons-external-1970474.ttf").await.map_err(|e| e.to_string())?;
-    let collector_response = reqwest::get("https://assetsnffrgf-a.akamaihd.net/assets/ct/1add6d1d93/collector.css").await.map_err(|e| e.to_string())?;
-
-    let total_size = font1_response.content_length().unwrap_or(0) + 
-        font2_response.content_length().unwrap_or(0) + 
-        collector_response.content_length().unwrap_or(1);
-
-    let mut downloaded: u64 = 0;
-
-    let data_filepath = app.path().app_local_data_dir().unwrap().join("www");
-    let fonts_filepath = data_filepath.join("fonts");
-
-    if !fonts_filepath.exists() {
-        fs::create_dir_all(&fonts_filepath).expect("Failed to create fonts directory");
-    }
-
-    let mut font1_file = fs::File::create(fonts_filepath.join("jw-icons-external-1970474.woff")).expect("Failed to create font1 file");
-    let mut font2_file = fs::File::create(fonts_filepath.join("jw-icons-external-1970474.ttf")).expect("Failed to create font2 file");
-    let mut collector_file = fs::File::create(data_filepath.join("collector.css")).expect("Failed to create collector file");
-
-    let mut stream = font1_response.bytes_stream();
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| e.to_string())?;
-        font1_file.write_all(&chunk).map_err(|e| e.to_string())?;
-        downloaded += chunk.len() as u64;
-
-        app.emit(
-            "download-progress",
-            downloaded as f64 / total_size as f64 * 100.0,
-        ).map_err(|e| e.to_string())?;
-    }
-
-    let mut stream = font2_response.bytes_stream();
+use super::downloads::DownloadManagerState;
 
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| e.to_string())?;
-        font2_file.write_all(&chunk).map_err(|e| e.to_string())?;
-        downloaded += chunk.len() as u64;
+const TARGET: &'static str = "commands::settings";
 
-        app.emit(
-            "download-progress",
-            downloaded as f64 / total_size as f64 * 100.0,
-        ).map_err(|e| e.to_string())?;
-    }
-
-    let mut stream = collector_response.bytes_stream();
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| e.to_string())?;
-        collector_file.write_all(&chunk).map_err(|e| e.to_string())?;
-        downloaded += chunk.len() as u64;
-
-        app.emit(
-            "download-progress",
-            downloaded as f64 / total_size as f64 * 100.0,
-        ).map_err(|e| e.to_string())?;
-    }
-
-    downloaded = total_size;
-
-    app.emit(
-        "download-progress",
-        downloaded as f64 / total_size as f64 * 100.0,
-    ).map_err(|e| e.to_string())?;
+#[tauri::command]
+pub async fn settings_download_base_assets(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DownloadManagerState>,
+) -> Result<(), String> {
+    let data_filepath = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("www");
+    let downloader = state.downloader.clone();
+
+    let progress_app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let manifest = BaseAssetManifest::bundled();
+        let statuses = manifest.validate(&data_filepath);
+
+        // Valid assets are kept, the others are downloaded again from scratch
+        let requests: Vec<DownloadRequest> = manifest
+            .assets
+            .iter()
+            .zip(&statuses)
+            .filter(|(_, status)| !status.state.is_present())
+            .map(|(asset, _)| {
+                let destination = data_filepath.join(&asset.path);
+                let _ = fs::remove_file(partial_path_of(&destination));
+                DownloadRequest {
+                    kind: DownloadKind::Asset,
+                    label: asset.path.clone(),
+                    url: asset.url.clone(),
+                    destination,
+                    expected: ExpectedFile {
+                        size: asset.size,
+                        md5: asset.md5.clone(),
+                    },
+                }
+            })
+            .collect();
+        info!(
+            target: TARGET,
+            "Downloading {} base assets of version {}...",
+            requests.len(),
+            manifest.version
+        );
+
+        // Latest (downloaded, total) of every asset, summed for the overall progress
+        let mut progress: HashMap<JobId, (u64, u64)> = HashMap::new();
+
+        downloader
+            .enqueue(requests)
+            .wait(|event| {
+                let total = match event.state {
+                    DownloadState::Completed => event.downloaded,
+                    _ => event.total,
+                };
+                progress.insert(event.job_id, (event.downloaded, total));
+
+                let (downloaded, total) =
+                    progress.values().fold((0, 0), |(downloaded, total), job| {
+                        (downloaded + job.0, total + job.1)
+                    });
+                if total > 0 {
+                    // 100 is only sent once every asset is in place
+                    let percent = (downloaded as f64 / total as f64 * 100.0).min(99.0);
+                    let _ = progress_app.emit("download-progress", percent);
+                }
+            })
+            .and_then(|_| manifest.record_installed(&data_filepath))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    app.emit("download-progress", 100.0)
+        .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -84,6 +97,24 @@ pub fn settings_base_assets_present(app: tauri::AppHandle) -> Result<(), Vec<Str
     is_base_assets_present(&app_data_dir)
 }
 
+/// State of every base asset against the bundled manifest.
+#[tauri::command]
+pub async fn settings_get_base_assets_status(
+    app: tauri::AppHandle,
+) -> Result<Vec<BaseAssetStatus>, String> {
+    let data_filepath = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("www");
+
+    tauri::async_runtime::spawn_blocking(move || {
+        BaseAssetManifest::bundled().validate(&data_filepath)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn settings_set_webview_theme(webview_window: tauri::WebviewWindow, theme: String) {
     #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
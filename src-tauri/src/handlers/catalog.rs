@@ -1,32 +1,63 @@
-use tauri::http::Response;
-use tokio::sync::MutexGuard;
+use std::path::{Component, Path};
 
-use crate::publib::Catalog;
+use percent_encoding::percent_decode_str;
+use tauri::http::{Request, Response, StatusCode};
 
-pub fn jwpub_media_handler<'a>(
-    manager: &mut MutexGuard<'a, Catalog>,
-    filename: &'a str,
+use crate::publib::{Catalog, MediaStore};
+
+use super::files::file_response;
+
+// Publication media doesn't change for an installed version
+const CACHE_CONTROL: &'static str = "public, max-age=604800";
+// Root path of the audio and video downloaded for offline use
+const STORED_MEDIA_ROOT: &'static str = "media";
+
+/// Serves `jwpub-media://localhost/{symbol}/{file}` requests, where `symbol`
+/// is the publication filename symbol (e.g. `nwtsty_E`).
+///
+/// Requests without a symbol (`jwpub-media://localhost/{file}`) are
+/// resolved on the current open publication, if any.
+///
+/// Audio and video stored for offline use are served at
+/// `jwpub-media://localhost/media/{file_path}`.
+pub fn jwpub_media_handler(
+    catalog: &Catalog,
+    store: &MediaStore,
+    request: &Request<Vec<u8>>,
 ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error>> {
-    let media_data = manager
-        .get_current_publication()
-        .expect("There's no publication open, unreachable")
-        .get_multimedia_data(filename.to_owned())?;
-    let extension = filename
-        .split(".")
-        .collect::<Vec<&str>>()
-        .last()
-        .unwrap()
-        .to_string();
-
-    let mime_type = match extension.as_str() {
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        _ => "application/octet-stream",
+    let path = percent_decode_str(request.uri().path()).decode_utf8()?;
+    let path = path.trim_start_matches('/');
+
+    let media_path = match path.split_once('/') {
+        Some((STORED_MEDIA_ROOT, file_path)) => store.get_media_file_path(file_path),
+        Some((filename_symbol, filename)) => {
+            catalog.get_publication_media_path(filename_symbol, filename)?
+        }
+        None if is_plain_filename(path) => catalog
+            .get_current_publication()
+            .map(|publication| publication.get_multimedia_path(path)),
+        None => None,
     };
 
-    Ok(Response::builder()
-        .status(200)
-        .header("Origin", "*")
-        .header("Content-Type", mime_type)
-        .body(media_data)?)
+    match media_path {
+        Some(media_path) => file_response(&media_path, request, CACHE_CONTROL),
+        None => {
+            warn!(
+                target: "jwpub-media::handler",
+                "Couldn't resolve media \"{}\" on any publication.", path
+            );
+            Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .header("Access-Control-Allow-Origin", "*")
+                .body(Vec::new())?)
+        }
+    }
+}
+
+fn is_plain_filename(filename: &str) -> bool {
+    let mut components = Path::new(filename).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
 }
@@ -0,0 +1,158 @@
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use tauri::http::{header, Request, Response, StatusCode};
+
+// Biggest chunk served by a single response, so media elements playing
+// big files don't load them whole into memory. Bigger ranges are cut, and
+// files bigger than this are answered by their first chunk.
+const MAX_RANGE_LENGTH: u64 = 2 * 1024 * 1024;
+
+pub fn mime_type_of(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "mp4" | "m4v" => "video/mp4",
+        "vtt" => "text/vtt",
+        "css" => "text/css",
+        "html" | "htm" => "text/html",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Parses a single `Range: bytes=...` header value into an inclusive
+/// byte range, at most `MAX_RANGE_LENGTH` long. Returns `None` when it
+/// can't be satisfied for `size`.
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let range = range.trim().strip_prefix("bytes=")?;
+    // Multiple ranges aren't supported, only the first one is served.
+    let range = range.split(',').next()?.trim();
+    let (start, end) = range.split_once('-')?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 {
+                return None;
+            }
+            (size.saturating_sub(suffix), size.checked_sub(1)?)
+        }
+        (start, "") => {
+            let start: u64 = start.parse().ok()?;
+            (start, size.checked_sub(1)?)
+        }
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end: u64 = end.parse().ok()?;
+            (start, end.min(size.checked_sub(1)?))
+        }
+    };
+
+    if start > end || start >= size {
+        return None;
+    }
+
+    Some((start, end.min(start + MAX_RANGE_LENGTH - 1)))
+}
+
+/// Serves a file from disk answering `Range` requests with partial content
+/// and conditional requests with `304 Not Modified`.
+pub fn file_response(
+    path: &Path,
+    request: &Request<Vec<u8>>,
+    cache_control: &str,
+) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error>> {
+    if !path.is_file() {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("Access-Control-Allow-Origin", "*")
+            .body(Vec::new())?);
+    }
+
+    let metadata = fs::metadata(path)?;
+    let size = metadata.len();
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let etag = format!("\"{:x}-{:x}\"", size, modified);
+
+    let response = Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Origin", "*")
+        .header(header::CONTENT_TYPE, mime_type_of(path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::ETAG, &etag);
+
+    if let Some(if_none_match) = request.headers().get(header::IF_NONE_MATCH) {
+        if if_none_match.to_str().unwrap_or_default() == etag {
+            return Ok(response.status(StatusCode::NOT_MODIFIED).body(Vec::new())?);
+        }
+    }
+
+    let mut file = fs::File::open(path)?;
+
+    // An unreadable header is ignored, like any other invalid range
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|range| range.to_str().ok());
+
+    let (start, end) = match range {
+        Some(range) => match parse_range(range, size) {
+            Some(range) => range,
+            None => {
+                return Ok(response
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+                    .body(Vec::new())?)
+            }
+        },
+        // Media elements ask for the rest by themselves
+        None if size > MAX_RANGE_LENGTH => (0, MAX_RANGE_LENGTH - 1),
+        None => {
+            let mut data = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut data)?;
+
+            return Ok(response
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, data.len())
+                .body(data)?);
+        }
+    };
+
+    let mut data = vec![0; (end - start + 1) as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut data)?;
+
+    Ok(response
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, size),
+        )
+        .header(header::CONTENT_LENGTH, data.len())
+        .body(data)?)
+}
@@ -1,42 +1,189 @@
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Component, PathBuf},
+};
 
-use tauri::http::Response;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use tauri::http::{header, Request, Response, StatusCode};
 
+use crate::publib::download::{verify_file, ExpectedFile};
+
+use super::files::file_response;
+
+// Bundled with the app, a new asset version ships with a new manifest
+const BASE_ASSETS_MANIFEST: &'static str = include_str!("../../assets/base-assets.json");
+// Served in place of `collector.css` until the base assets are downloaded
+const FALLBACK_STYLESHEET: &'static str = include_str!("../../assets/fallback-reader.css");
+const STYLESHEET: &'static str = "collector.css";
+// Copy of the manifest written next to the installed assets, telling
+// which version they were downloaded from
+const INSTALLED_MANIFEST: &'static str = "base-assets.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BaseAsset {
+    /// Location inside of the `www` directory.
+    pub path: String,
+    pub url: String,
+    pub size: Option<u64>,
+    pub md5: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BaseAssetManifest {
+    pub version: String,
+    pub assets: Vec<BaseAsset>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BaseAssetState {
+    Valid,
+    Missing,
+    /// Installed from another manifest version.
+    Outdated,
+    /// Size or hash doesn't match the manifest.
+    Corrupt,
+    /// Installed from this manifest version, which has no size or hash
+    /// to check it against.
+    Unverified,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BaseAssetStatus {
+    pub path: String,
+    pub state: BaseAssetState,
+}
+
+impl BaseAssetState {
+    /// Whether the asset can be used as is, without downloading it again.
+    /// Only assets matching the size and hash of the manifest are.
+    pub fn is_present(&self) -> bool {
+        matches!(self, Self::Valid)
+    }
+}
+
+impl BaseAssetManifest {
+    pub fn bundled() -> Self {
+        serde_json::from_str(BASE_ASSETS_MANIFEST).expect("Invalid bundled base asset manifest")
+    }
+
+    pub fn installed(data_path: &PathBuf) -> Option<Self> {
+        let file = fs::File::open(data_path.join(INSTALLED_MANIFEST)).ok()?;
+        serde_json::from_reader(file).ok()
+    }
+
+    /// Writes the manifest as installed in `data_path`.
+    pub fn record_installed(&self, data_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        let file = fs::File::create(data_path.join(INSTALLED_MANIFEST))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Checks every asset of the manifest installed in `data_path` against
+    /// the size and hash listed in the manifest.
+    pub fn validate(&self, data_path: &PathBuf) -> Vec<BaseAssetStatus> {
+        let installed_version = Self::installed(data_path).map(|installed| installed.version);
+
+        self.assets
+            .iter()
+            .map(|asset| {
+                let path = data_path.join(&asset.path);
+                let expected = ExpectedFile {
+                    size: asset.size,
+                    md5: asset.md5.clone(),
+                };
+
+                let state = if !path.exists() {
+                    BaseAssetState::Missing
+                } else if installed_version.as_ref() != Some(&self.version) {
+                    BaseAssetState::Outdated
+                } else if verify_file(&path, &expected).is_err() {
+                    BaseAssetState::Corrupt
+                } else if expected.size.is_none() || expected.md5.is_none() {
+                    BaseAssetState::Unverified
+                } else {
+                    BaseAssetState::Valid
+                };
+
+                BaseAssetStatus {
+                    path: asset.path.clone(),
+                    state,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Lists the base assets that are missing or invalid in `data_path`.
 pub fn is_base_assets_present<'a>(data_path: &'a PathBuf) -> Result<(), Vec<String>> {
-    let styles_path = vec![
-        data_path.join("collector.css"),
-        data_path.join(PathBuf::from_iter([
-            "fonts",
-            "jw-icons-external-1970474.ttf",
-        ])),
-        data_path.join(PathBuf::from_iter([
-            "fonts",
-            "jw-icons-external-1970474.woff",
-        ])),
-    ];
-
-    let mut missing_paths = vec![];
-
-    for path in &styles_path {
-        if !path.exists() {
-            missing_paths.push(path.to_string_lossy().to_string());
-        }
-    }
-
-    if missing_paths.is_empty() {
+    let invalid_paths: Vec<String> = BaseAssetManifest::bundled()
+        .validate(data_path)
+        .into_iter()
+        .filter(|status| !status.state.is_present())
+        .map(|status| data_path.join(status.path).to_string_lossy().to_string())
+        .collect();
+
+    if invalid_paths.is_empty() {
         Ok(())
     } else {
-        Err(missing_paths)
+        Err(invalid_paths)
     }
 }
 
+/// Serves `appdata://localhost/{path}` requests from files inside
+/// `data_path`. Paths resolving outside of it are forbidden.
+///
+/// A missing `collector.css` is answered with a minimal built-in reader
+/// stylesheet, so documents stay readable without the base assets.
 pub fn appdata_handler<'a>(
-    resolved_path: &'a PathBuf,
+    data_path: &'a PathBuf,
+    request: &Request<Vec<u8>>,
 ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error>> {
-    let media_data = std::fs::read(resolved_path)?;
+    let file = percent_decode_str(request.uri().path()).decode_utf8()?;
+    let relative_path = PathBuf::from_iter(file.split('/').filter(|part| !part.is_empty()));
+
+    if relative_path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return status_response(StatusCode::FORBIDDEN);
+    }
+
+    if relative_path.as_os_str() == STYLESHEET && !data_path.join(&relative_path).exists() {
+        return fallback_stylesheet_response();
+    }
+
+    let (Ok(data_root), Ok(resolved_path)) = (
+        data_path.canonicalize(),
+        data_path.join(&relative_path).canonicalize(),
+    ) else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+
+    // Symbolic links may still point outside of data root
+    if !resolved_path.starts_with(&data_root) {
+        return status_response(StatusCode::FORBIDDEN);
+    }
+
+    file_response(&resolved_path, request, "no-cache")
+}
+
+fn fallback_stylesheet_response() -> Result<Response<Vec<u8>>, Box<dyn std::error::Error>> {
+    debug!(
+        target: "appdata::handler",
+        "Base stylesheet missing, serving the fallback one."
+    );
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "text/css")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header("Access-Control-Allow-Origin", "*")
+        .body(FALLBACK_STYLESHEET.as_bytes().to_vec())?)
+}
+
+fn status_response(status: StatusCode) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error>> {
     Ok(Response::builder()
-        .status(200)
+        .status(status)
         .header("Access-Control-Allow-Origin", "*")
-        .header("Origin", "*")
-        .body(media_data)?)
+        .body(Vec::new())?)
 }
@@ -6,10 +6,11 @@ pub mod utils;
 use commands::catalogue::CatalogManager;
 use handlers::catalog::jwpub_media_handler;
 use handlers::www::appdata_handler;
-use tauri::async_runtime::Mutex;
-use tauri::{http::Response, Manager};
+use std::sync::Arc;
 
-use commands::{catalogue, settings};
+use tauri::{http::Response, Emitter, Manager};
+
+use commands::{alerts, catalogue, downloads, languages, media, remote, settings};
 use tauri_plugin_log::fern::colors::{Color, ColoredLevelConfig};
 
 #[macro_use]
@@ -68,16 +69,68 @@ pub fn run() {
             catalogue::catalog_install_jwpub_file,
             catalogue::catalog_get_list_from_type,
             catalogue::catalog_get_count_from_type,
+            catalogue::catalog_get_periodicals_from_type,
             catalogue::catalog_open_connection,
             catalogue::catalog_get_publication_view_from,
             catalogue::catalog_check_document_exists,
             catalogue::catalog_get_documents,
             catalogue::catalog_get_document_by_id,
             catalogue::catalog_get_document_content,
+            catalogue::catalog_get_documents_from,
+            catalogue::catalog_get_document_by_id_from,
+            catalogue::catalog_check_document_exists_from,
+            catalogue::catalog_get_document_content_from,
             catalogue::catalog_get_images_of_type,
+            catalogue::catalog_get_dated_text,
+            catalogue::catalog_get_meeting_week,
+            catalogue::catalog_get_topics,
+            catalogue::catalog_search_topics,
+            catalogue::catalog_get_document_extracts,
+            catalogue::catalog_get_related_documents,
+            catalogue::catalog_get_document_media,
+            catalogue::catalog_get_paragraph_video_segments,
+            catalogue::catalog_get_verse_video_segments,
+            catalogue::catalog_get_extract_video_segments,
+            catalogue::catalog_get_document_languages,
+            catalogue::catalog_switch_document_language,
+            catalogue::catalog_get_bilingual_document,
+            remote::remote_get_base_url,
+            remote::remote_set_base_url,
+            remote::remote_set_pub_media_base_url,
+            remote::remote_get_manifest,
+            remote::remote_refresh_catalog,
+            remote::remote_get_languages,
+            remote::remote_get_publication_types,
+            remote::remote_get_years,
+            remote::remote_get_publications,
+            remote::remote_download_publication,
+            remote::remote_cancel_download,
+            remote::remote_check_updates,
+            remote::remote_apply_updates,
+            media::media_get_languages,
+            media::media_get_catalog_items,
+            media::media_get_publication_media,
+            media::media_get_document_media,
+            media::media_store_get_media,
+            media::media_store_get_usage,
+            media::media_store_set_quota,
+            media::media_store_download,
+            media::media_store_delete,
+            media::media_store_delete_publication_media,
+            languages::languages_get_all,
+            languages::languages_get,
+            languages::languages_get_installed,
+            languages::languages_refresh,
+            alerts::alerts_get_base_url,
+            alerts::alerts_set_base_url,
+            alerts::alerts_get_alerts,
+            alerts::alerts_set_read,
+            downloads::downloads_get_jobs,
+            downloads::downloads_cancel,
             settings::settings_set_webview_theme,
             settings::settings_base_assets_present,
             settings::settings_download_base_assets,
+            settings::settings_get_base_assets_status,
         ])
         .setup(|app| {
             info!(
@@ -91,7 +144,7 @@ pub fn run() {
                     .green()
             );
             app.manage(catalogue::CatalogManager {
-                catalog: Mutex::new(
+                catalog: Arc::new(
                     publib::Catalog::init(
                         app.path()
                             .app_local_data_dir()
@@ -101,56 +154,144 @@ pub fn run() {
                     .expect("Couldn't initialize catalog"),
                 ),
             });
+            // Shared by every download so transfers are queued together
+            let app_handle = app.handle().clone();
+            let downloader = Arc::new(publib::DownloadManager::new(3, move |event| {
+                let _ = app_handle.emit("download-job-progress", event);
+            }));
+            app.manage(downloads::DownloadManagerState {
+                downloader: downloader.clone(),
+            });
+            // Both servers can be swapped, e.g. for a local mock server
+            let remote_base_url = std::env::var("OWL_REMOTE_BASE_URL")
+                .unwrap_or(publib::remote::DEFAULT_BASE_URL.to_owned());
+            let pub_media_base_url = std::env::var("OWL_PUB_MEDIA_BASE_URL")
+                .unwrap_or(publib::remote::DEFAULT_PUB_MEDIA_BASE_URL.to_owned());
+            let remote_catalog = Arc::new(
+                publib::RemoteCatalog::init(
+                    app.path().app_local_data_dir().unwrap().join("catalogs"),
+                    &remote_base_url,
+                    &pub_media_base_url,
+                    downloader.clone(),
+                )
+                .expect("Couldn't initialize remote catalog"),
+            );
+            app.manage(media::MediaStoreManager {
+                store: Arc::new(
+                    publib::MediaStore::init(
+                        app.path()
+                            .app_local_data_dir()
+                            .unwrap()
+                            .join("publications"),
+                        downloader.clone(),
+                    )
+                    .expect("Couldn't initialize media store"),
+                ),
+            });
+            app.manage(media::MediaCatalogManager {
+                media: Arc::new(
+                    publib::MediaCatalog::init(
+                        app.path()
+                            .app_local_data_dir()
+                            .unwrap()
+                            .join("catalogs")
+                            .join("media"),
+                        remote_catalog.clone(),
+                        downloader,
+                    )
+                    .expect("Couldn't initialize media catalog"),
+                ),
+            });
+            app.manage(remote::RemoteCatalogManager {
+                remote: remote_catalog,
+            });
+            app.manage(languages::LanguageRegistryManager {
+                registry: Arc::new(
+                    publib::LanguageRegistry::init(
+                        app.path()
+                            .app_local_data_dir()
+                            .unwrap()
+                            .join("catalogs")
+                            .join("languages.json"),
+                    )
+                    .expect("Couldn't initialize language registry"),
+                ),
+            });
+            let alerts_base_url =
+                std::env::var("OWL_ALERTS_BASE_URL").unwrap_or(remote_base_url.clone());
+            app.manage(alerts::AlertsManager {
+                alerts: Arc::new(
+                    publib::AlertsClient::init(
+                        app.path().app_local_data_dir().unwrap().join("alerts"),
+                        &alerts_base_url,
+                    )
+                    .expect("Couldn't initialize alerts client"),
+                ),
+            });
+            remote::spawn_update_check(app.handle().clone());
             Ok(())
         })
-        .register_uri_scheme_protocol("jwpub-media", |ctx, req| {
-            let app = ctx.app_handle();
-            let filename = req.uri().path().replace("/", "");
-            let state = app.state::<CatalogManager>();
-            let mut manager = tauri::async_runtime::block_on(state.catalog.lock());
+        // Both protocols read files from disk, so responses are built on a
+        // blocking thread instead of the webview one.
+        .register_asynchronous_uri_scheme_protocol("jwpub-media", |ctx, req, responder| {
+            let catalog = ctx.app_handle().state::<CatalogManager>().catalog.clone();
+            let store = ctx
+                .app_handle()
+                .state::<media::MediaStoreManager>()
+                .store
+                .clone();
 
-            match jwpub_media_handler(&mut manager, &filename).map_err(|err| err.to_string()) {
-                Ok(response) => response,
-                Err(err) => {
-                    error!(
-                        target: "jwpub-media::handler",
-                        "Error handling jwpub-media request: {}",
-                        err.red()
-                    );
-                    Response::builder()
-                        .status(500)
-                        .body(err.into_bytes())
-                        .unwrap()
-                }
-            }
+            tauri::async_runtime::spawn_blocking(move || {
+                let response = match jwpub_media_handler(&catalog, &store, &req)
+                    .map_err(|err| err.to_string())
+                {
+                    Ok(response) => response,
+                    Err(err) => {
+                        error!(
+                            target: "jwpub-media::handler",
+                            "Error handling jwpub-media request: {}",
+                            err.red()
+                        );
+                        Response::builder()
+                            .status(500)
+                            .body(err.into_bytes())
+                            .unwrap()
+                    }
+                };
+                responder.respond(response);
+            });
         })
-        .register_uri_scheme_protocol("appdata", |ctx, req| {
-            let app = ctx.app_handle();
-            let file: String = req.uri().path().replacen("/", "", 1);
-            let mut filepath = app.path().app_local_data_dir().unwrap().join("www");
-            filepath.extend(file.split("/"));
+        .register_asynchronous_uri_scheme_protocol("appdata", |ctx, req, responder| {
+            let data_path = ctx
+                .app_handle()
+                .path()
+                .app_local_data_dir()
+                .unwrap()
+                .join("www");
 
-            match appdata_handler(&filepath).map_err(|err| err.to_string()) {
-                Ok(response) => response,
-                Err(err) => {
-                    error!(
-                        target: "appdata::handler",
-                        "Error handling appdata request: {}",
-                        err.red()
-                    );
-                    Response::builder()
-                        .status(500)
-                        .body(err.into_bytes())
-                        .unwrap()
-                }
-            }
+            tauri::async_runtime::spawn_blocking(move || {
+                let response =
+                    match appdata_handler(&data_path, &req).map_err(|err| err.to_string()) {
+                        Ok(response) => response,
+                        Err(err) => {
+                            error!(
+                                target: "appdata::handler",
+                                "Error handling appdata request: {}",
+                                err.red()
+                            );
+                            Response::builder()
+                                .status(500)
+                                .body(err.into_bytes())
+                                .unwrap()
+                        }
+                    };
+                responder.respond(response);
+            });
         })
         // .register_uri_scheme_protocol("jwpub-media", |ctx, req| {
         // }) // TODO: Refactor jwpub using discoveries from Document contents
         // TODO: Use MepsDocumentId table for opening other pub parts
         // https://b.jw-cdn.org/apis/pub-media/GETPUBMEDIALINKS?output=json&docid={MepsDocumentId}&langwritten=T
-        // https://app.jw-cdn.org/catalogs/publications/v4/manifest.json
-        // https://app.jw-cdn.org/catalogs/publications/v4/{current_from_manifest.json_above}/catalog.db.gz
         // .register_uri_scheme_protocol("jwpub", |ctx, req| {
         // })
         .run(tauri::generate_context!())
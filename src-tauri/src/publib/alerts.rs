@@ -0,0 +1,420 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    languages::{check_language_code, is_valid_language_code},
+    remote::http_client,
+};
+
+const TARGET: &'static str = "catalog::alerts";
+
+const TOKEN_PATH: &'static str = "tokens/jwl-public.jwt";
+const ALERTS_PATH: &'static str = "apis/alerts/list";
+const ALERT_TYPE: &'static str = "news";
+
+// Alerts are fetched again once older than this, or used as is when offline
+const ALERTS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+// The public token is short lived, and fetched again on rejection anyway
+const TOKEN_MAX_AGE: Duration = Duration::from_secs(30 * 60);
+// Alerts of each language are cached in this directory, apart from the
+// read state
+const LANGUAGES_DIR: &'static str = "languages";
+const READ_FILE: &'static str = "read.json";
+
+/// News or alert published on the alert API.
+// Read in camel case from the API, and as serialized from the cache
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Alert {
+    pub guid: String,
+    #[serde(alias = "languageCode")]
+    pub language_code: Option<String>,
+    #[serde(rename = "type")]
+    pub alert_type: Option<String>,
+    pub title: Option<String>,
+    /// HTML contents.
+    pub body: Option<String>,
+    #[serde(alias = "startDate")]
+    pub start_date: Option<String>,
+    #[serde(alias = "endDate")]
+    pub end_date: Option<String>,
+    /// Set locally, not by the API.
+    #[serde(default)]
+    pub read: bool,
+}
+
+// The list has been served both bare and wrapped
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AlertList {
+    Wrapped { alerts: Vec<Alert> },
+    List(Vec<Alert>),
+}
+
+// Alerts of a language as cached on disk
+#[derive(Serialize, Deserialize, Clone)]
+struct CachedAlerts {
+    fetched_at: SystemTime,
+    alerts: Vec<Alert>,
+}
+
+/// Client of the public alert API, caching alerts per language and
+/// keeping their read state locally.
+pub struct AlertsClient {
+    cache_path: PathBuf,
+    base_url: Mutex<String>,
+
+    token: Mutex<Option<(String, SystemTime)>>,
+    alerts: Mutex<HashMap<String, CachedAlerts>>,
+    read: Mutex<HashSet<String>>,
+}
+
+impl AlertsClient {
+    pub fn init<T: Into<PathBuf>>(
+        location: T,
+        base_url: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let cache_path: PathBuf = location.into();
+        if !cache_path.join(LANGUAGES_DIR).exists() {
+            fs::create_dir_all(cache_path.join(LANGUAGES_DIR))?;
+        }
+
+        let read = fs::File::open(cache_path.join(READ_FILE))
+            .ok()
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            cache_path,
+            base_url: Mutex::new(base_url.trim_end_matches('/').to_owned()),
+            token: Mutex::new(None),
+            alerts: Mutex::new(HashMap::new()),
+            read: Mutex::new(read),
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        self.base_url
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Changes the server alerts are fetched from, e.g. a local stub.
+    /// Cached alerts and token are dropped.
+    pub fn set_base_url(&self, base_url: &str) {
+        info!(target: TARGET, "Alerts base URL set to {}.", base_url.green());
+        *self.base_url.lock().unwrap_or_else(PoisonError::into_inner) =
+            base_url.trim_end_matches('/').to_owned();
+        *self.token.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        self.alerts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        for language_code in self.cached_languages() {
+            let _ = fs::remove_file(self.cache_file(&language_code));
+        }
+    }
+
+    /// Current alerts of `language_code`, newest first. Cached alerts are
+    /// used until they expire, unless `refresh` is set, and when the API
+    /// can't be reached.
+    pub fn get_alerts(
+        &self,
+        language_code: &str,
+        refresh: bool,
+    ) -> Result<Vec<Alert>, Box<dyn std::error::Error>> {
+        check_language_code(language_code)?;
+
+        let cached = self.cached_alerts(language_code);
+        let fresh = matches!(&cached, Some(cached) if cached
+            .fetched_at
+            .elapsed()
+            .map(|age| age < ALERTS_MAX_AGE)
+            .unwrap_or(false));
+
+        let mut alerts = match cached {
+            Some(cached) if fresh && !refresh => cached.alerts,
+            cached => match self.fetch_alerts(language_code) {
+                Ok(alerts) => {
+                    self.store_alerts(
+                        language_code,
+                        CachedAlerts {
+                            fetched_at: SystemTime::now(),
+                            alerts: alerts.clone(),
+                        },
+                    );
+                    alerts
+                }
+                Err(err) => match cached {
+                    Some(cached) => {
+                        warn!(target: TARGET, "Couldn't fetch alerts, using the cached ones: {}", err);
+                        cached.alerts
+                    }
+                    None => return Err(err),
+                },
+            },
+        };
+
+        let now = Utc::now();
+        alerts.retain(
+            |alert| match alert.end_date.as_deref().and_then(parse_date) {
+                Some(end_date) => end_date > now,
+                None => true,
+            },
+        );
+        alerts.sort_by(|a, b| b.start_date.cmp(&a.start_date));
+
+        let read = self.read.lock().unwrap_or_else(PoisonError::into_inner);
+        for alert in &mut alerts {
+            alert.read = read.contains(&alert.guid);
+        }
+
+        Ok(alerts)
+    }
+
+    /// Marks an alert as read or unread.
+    pub fn set_read(&self, guid: &str, is_read: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let mut read = self.read.lock().unwrap_or_else(PoisonError::into_inner);
+        if is_read {
+            read.insert(guid.to_owned());
+        } else {
+            read.remove(guid);
+        }
+
+        let file = fs::File::create(self.cache_path.join(READ_FILE))?;
+        serde_json::to_writer(file, &*read)?;
+        Ok(())
+    }
+
+    fn fetch_alerts(&self, language_code: &str) -> Result<Vec<Alert>, Box<dyn std::error::Error>> {
+        let url = format!("{}/{}", self.base_url(), ALERTS_PATH);
+        debug!(target: TARGET, "Fetching {} alerts from {}...", language_code.bright_magenta(), url.green());
+
+        let client = http_client()?;
+        let request = |token: &str| {
+            client
+                .get(&url)
+                .query(&[("type", ALERT_TYPE), ("lang", language_code)])
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .send()
+        };
+
+        let mut response = request(&self.token(false)?)?;
+        // An expired token is rejected, a new one is fetched once
+        if matches!(
+            response.status(),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) {
+            response = request(&self.token(true)?)?;
+        }
+
+        Ok(
+            match serde_json::from_reader(response.error_for_status()?)? {
+                AlertList::Wrapped { alerts } => alerts,
+                AlertList::List(alerts) => alerts,
+            },
+        )
+    }
+
+    fn token(&self, refresh: bool) -> Result<String, Box<dyn std::error::Error>> {
+        let mut token = self.token.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((value, fetched_at)) = token.as_ref() {
+            let fresh = fetched_at
+                .elapsed()
+                .map(|age| age < TOKEN_MAX_AGE)
+                .unwrap_or(false);
+            if fresh && !refresh {
+                return Ok(value.clone());
+            }
+        }
+
+        let url = format!("{}/{}", self.base_url(), TOKEN_PATH);
+        debug!(target: TARGET, "Fetching alert API token from {}...", url.green());
+        let value = http_client()?
+            .get(&url)
+            .send()?
+            .error_for_status()?
+            .text()?
+            .trim()
+            .to_owned();
+        *token = Some((value.clone(), SystemTime::now()));
+
+        Ok(value)
+    }
+
+    // Expects a code checked by `is_valid_language_code`
+    fn cache_file(&self, language_code: &str) -> PathBuf {
+        self.cache_path
+            .join(LANGUAGES_DIR)
+            .join(format!("{}.json", language_code))
+    }
+
+    fn cached_languages(&self) -> Vec<String> {
+        fs::read_dir(self.cache_path.join(LANGUAGES_DIR))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter_map(|entry| {
+                        let name = entry.file_name().to_string_lossy().to_string();
+                        name.strip_suffix(".json")
+                            .filter(|language_code| is_valid_language_code(language_code))
+                            .map(|language_code| language_code.to_owned())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn cached_alerts(&self, language_code: &str) -> Option<CachedAlerts> {
+        if let Some(cached) = self
+            .alerts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(language_code)
+        {
+            return Some(cached.clone());
+        }
+
+        let file = fs::File::open(self.cache_file(language_code)).ok()?;
+        serde_json::from_reader(file).ok()
+    }
+
+    fn store_alerts(&self, language_code: &str, cached: CachedAlerts) {
+        match fs::File::create(self.cache_file(language_code)) {
+            Ok(file) => {
+                if let Err(err) = serde_json::to_writer(file, &cached) {
+                    warn!(target: TARGET, "Couldn't cache alerts: {}", err);
+                }
+            }
+            Err(err) => warn!(target: TARGET, "Couldn't cache alerts: {}", err),
+        }
+
+        self.alerts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(language_code.to_owned(), cached);
+    }
+}
+
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::publib::test_server::{TestResponse, TestServer};
+
+    // Hands out `token-1`, `token-2`... and only accepts the latest one
+    fn alerts_server() -> TestServer {
+        let tokens = AtomicUsize::new(0);
+        TestServer::start(move |request| {
+            let path = request.path.split('?').next().unwrap_or_default();
+            if path == format!("/{}", TOKEN_PATH) {
+                let token = tokens.fetch_add(1, Ordering::SeqCst) + 1;
+                TestResponse::ok(format!("token-{}\n", token))
+            } else if path == format!("/{}", ALERTS_PATH) {
+                let latest = format!("Bearer token-{}", tokens.load(Ordering::SeqCst));
+                if request.headers.get("authorization") != Some(&latest) {
+                    return TestResponse::status(401);
+                }
+                TestResponse::ok(
+                    r#"{"alerts": [
+                        {"guid": "a", "languageCode": "E", "title": "Older", "startDate": "2024-01-01T00:00:00Z"},
+                        {"guid": "b", "languageCode": "E", "title": "Newer", "startDate": "2024-02-01T00:00:00Z"},
+                        {"guid": "c", "languageCode": "E", "title": "Ended", "endDate": "2000-01-01T00:00:00Z"}
+                    ]}"#,
+                )
+            } else {
+                TestResponse::status(404)
+            }
+        })
+    }
+
+    fn alert_titles(alerts: &[Alert]) -> Vec<(&str, bool)> {
+        alerts
+            .iter()
+            .map(|alert| (alert.title.as_deref().unwrap_or_default(), alert.read))
+            .collect()
+    }
+
+    #[test]
+    fn rejected_token_is_fetched_again() {
+        let server = alerts_server();
+        let cache = tempfile::tempdir().unwrap();
+        let client = AlertsClient::init(cache.path(), &server.base_url).unwrap();
+
+        // Some token the server no longer accepts
+        *client.token.lock().unwrap() = Some(("expired".to_owned(), SystemTime::now()));
+        let alerts = client.get_alerts("E", false).unwrap();
+        assert_eq!(alert_titles(&alerts), [("Newer", false), ("Older", false)]);
+
+        let alert_requests: Vec<String> = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.path.starts_with(&format!("/{}", ALERTS_PATH)))
+            .map(|request| request.headers["authorization"].clone())
+            .collect();
+        assert_eq!(alert_requests, ["Bearer expired", "Bearer token-1"]);
+        assert!(server.requests()[2].path.contains("lang=E"));
+    }
+
+    #[test]
+    fn read_state_is_kept() {
+        let server = alerts_server();
+        let cache = tempfile::tempdir().unwrap();
+        let client = AlertsClient::init(cache.path(), &server.base_url).unwrap();
+
+        client.get_alerts("E", false).unwrap();
+        client.set_read("a", true).unwrap();
+        assert_eq!(
+            alert_titles(&client.get_alerts("E", false).unwrap()),
+            [("Newer", false), ("Older", true)]
+        );
+
+        // Kept across restarts, alerts come from the cache
+        let client = AlertsClient::init(cache.path(), &server.base_url).unwrap();
+        assert_eq!(
+            alert_titles(&client.get_alerts("E", false).unwrap()),
+            [("Newer", false), ("Older", true)]
+        );
+        assert_eq!(server.hits(&format!("/{}", ALERTS_PATH)), 1);
+
+        client.set_read("a", false).unwrap();
+        assert_eq!(
+            alert_titles(&client.get_alerts("E", true).unwrap()),
+            [("Newer", false), ("Older", false)]
+        );
+    }
+
+    #[test]
+    fn language_code_stays_in_cache() {
+        let server = alerts_server();
+        let cache = tempfile::tempdir().unwrap();
+        let client = AlertsClient::init(cache.path(), &server.base_url).unwrap();
+        client.set_read("a", true).unwrap();
+
+        for language_code in ["", "../E", "E/x", "."] {
+            assert!(client.get_alerts(language_code, false).is_err());
+        }
+        // Doesn't overwrite the read state
+        client.get_alerts("read", false).unwrap();
+        let client = AlertsClient::init(cache.path(), &server.base_url).unwrap();
+        assert!(client.read.lock().unwrap().contains("a"));
+        assert_eq!(server.hits(&format!("/{}", ALERTS_PATH)), 1);
+    }
+}
@@ -1,32 +1,57 @@
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     io::{self, Cursor, Read, Seek},
     num::NonZero,
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
 };
 
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use colored::Colorize;
 use lru::LruCache;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, Row};
 use serde::{Deserialize, Serialize};
 use zip::ZipArchive;
 
 use crate::utils::unpack_zip;
 
 use super::{
+    dates::{self, IssuePeriod},
     manifest::{Image, IssueProperties},
+    pool::ConnectionPool,
+    publication::{
+        ContentTables, DocumentMedia, ExtractVideoSegment, PublicationExtract,
+        PublicationParagraph, PublicationTopic, VideoSegment,
+    },
+    tables::RelatedDocument,
     Manifest, Publication,
 };
 
 const TARGET: &'static str = "catalog";
 
+// `KeySymbol` shared by every "Examining the Scriptures Daily" edition
+const DAILY_TEXT_KEY_SYMBOL: &'static str = "es";
+// `KeySymbol` of meeting workbook and Watchtower issues
+const MEETING_WORKBOOK_KEY_SYMBOL: &'static str = "mwb";
+const WATCHTOWER_KEY_SYMBOL: &'static str = "w";
+
+// Idle read connections kept open for the catalog database
+const MAX_IDLE_READERS: usize = 4;
+
 pub struct Catalog {
     pub_path: PathBuf,
-    catalog_db: Connection,
 
-    current_open: String,
-    publication_cache: LruCache<String, Publication>,
+    // Reads go through the pool so they never wait on an install, every
+    // write is serialized through the single writer connection.
+    readers: ConnectionPool,
+    writer: Mutex<Connection>,
+
+    // Kept for commands acting on the last opened publication, every
+    // publication can be reached by symbol with `get_publication`.
+    current_open: Mutex<Option<String>>,
+    publication_cache: Mutex<LruCache<String, Arc<Publication>>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -71,6 +96,70 @@ pub struct CollectionPublication {
     pub min_platform_version: i32,
     pub key_symbol: String,
     pub meps_build_number: i32,
+
+    // Typed views of the date offsets and issue tag above
+    pub first_dated_text_date: Option<NaiveDate>,
+    pub last_dated_text_date: Option<NaiveDate>,
+    pub issue_period: Option<IssuePeriod>,
+    pub issue_date: Option<NaiveDate>,
+}
+
+impl CollectionPublication {
+    // Expects the column order used by `Publication` queries on this module.
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let first_dated_text_date_offset: i32 = row.get(12)?;
+        let last_dated_text_date_offset: i32 = row.get(13)?;
+        let issue_tag_number: Option<i32> = row.get(11)?;
+        let issue_period = issue_tag_number.and_then(IssuePeriod::from_issue_tag);
+
+        Ok(Self {
+            id: row.get(0)?,
+            language_index: row.get(1)?,
+            publication_type: row.get(2)?,
+            publication_category_symbol: row.get(3)?,
+            title: row.get(4)?,
+            short_title: row.get(5)?,
+            display_title: row.get(6)?,
+            symbol: row.get(7)?,
+            unique_english_symbol: row.get(8)?,
+            year: row.get(9)?,
+            volume_number: row.get(10)?,
+            issue_tag_number,
+            first_dated_text_date_offset,
+            last_dated_text_date_offset,
+            root_symbol: row.get(14)?,
+            root_year: row.get(15)?,
+            root_meps_language_index: row.get(16)?,
+            version_number: row.get(17)?,
+            schema_version_number: row.get(18)?,
+            hash: row.get(19)?,
+            timestamp: row.get(20)?,
+            jwpub: row.get(21)?,
+            database_path: row.get(22)?,
+            on_external_storage: row.get(23)?,
+            undated_reference_title: row.get(24)?,
+            expanded_size: row.get(25)?,
+            min_platform_version: row.get(26)?,
+            key_symbol: row.get(27)?,
+            meps_build_number: row.get(28)?,
+            first_dated_text_date: dates::date_from_offset(first_dated_text_date_offset),
+            last_dated_text_date: dates::date_from_offset(last_dated_text_date_offset),
+            issue_period,
+            issue_date: issue_period.and_then(|period| period.start_date()),
+        })
+    }
+
+    /// Language code the publication was released with, taken from its
+    /// package name (e.g. `E` for `nwtsty_E.jwpub` or `w_E_202411.jwpub`).
+    pub fn language_code(&self) -> Option<&str> {
+        let name = self.jwpub.strip_suffix(".jwpub").unwrap_or(&self.jwpub);
+        let rest = [self.symbol.as_str(), self.key_symbol.as_str()]
+            .iter()
+            .filter(|symbol| !symbol.is_empty())
+            .find_map(|symbol| name.strip_prefix(*symbol)?.strip_prefix('_'))?;
+
+        rest.split('_').next().filter(|code| !code.is_empty())
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -85,6 +174,185 @@ pub struct CollectionImage {
     pub signature: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct CollectionDatedText {
+    pub publication_id: i64,
+    pub publication_symbol: String,
+    pub document_id: i32,
+
+    pub first_date_offset: i32,
+    pub last_date_offset: i32,
+    pub first_date: Option<NaiveDate>,
+    pub last_date: Option<NaiveDate>,
+
+    pub caption: String,
+    pub caption_rich: Option<String>,
+
+    // Decrypted HTML content
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionScheduledDocument {
+    pub publication_id: i64,
+    pub publication_symbol: String,
+    pub issue_tag_number: Option<i32>,
+
+    pub document_id: i32,
+    pub meps_document_id: i32,
+    pub title: String,
+
+    // Dated text caption, usually the week range
+    pub caption: String,
+    pub first_date_offset: i32,
+    pub last_date_offset: i32,
+    pub first_date: Option<NaiveDate>,
+    pub last_date: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionMeetingWeek {
+    pub date: NaiveDate,
+    pub date_offset: i32,
+    pub meeting_workbook: Option<CollectionScheduledDocument>,
+    pub watchtower_study: Option<CollectionScheduledDocument>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionIssueProperty {
+    pub title: String,
+    pub undated_title: String,
+    pub cover_title: String,
+    pub symbol: String,
+    pub undated_symbol: String,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodicalEdition {
+    Study,
+    Public,
+    Simplified,
+    General,
+}
+
+impl PeriodicalEdition {
+    pub fn from_issue_attributes(issue_attributes: &[String]) -> Self {
+        let has_attribute = |name: &str| {
+            issue_attributes
+                .iter()
+                .any(|attribute| attribute.to_lowercase().contains(name))
+        };
+
+        if has_attribute("simplified") {
+            Self::Simplified
+        } else if has_attribute("study") {
+            Self::Study
+        } else if has_attribute("public") {
+            Self::Public
+        } else {
+            Self::General
+        }
+    }
+}
+
+// One edition of a periodical issue, e.g. the study edition
+#[derive(Serialize, Deserialize)]
+pub struct CollectionPeriodicalEditionItem {
+    pub publication: CollectionPublication,
+    pub issue_property: Option<CollectionIssueProperty>,
+    pub issue_attributes: Vec<String>,
+    pub edition: PeriodicalEdition,
+    pub images: Vec<CollectionImage>,
+}
+
+// Editions sharing the same `IssueTagNumber`
+#[derive(Serialize, Deserialize)]
+pub struct CollectionPeriodicalIssue {
+    pub issue_tag_number: Option<i32>,
+    pub issue_period: Option<IssuePeriod>,
+    pub issue_date: Option<NaiveDate>,
+    pub editions: Vec<CollectionPeriodicalEditionItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionPeriodicalYear {
+    pub year: i32,
+    pub issues: Vec<CollectionPeriodicalIssue>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionPeriodicalGroup {
+    pub root_symbol: String,
+    pub years: Vec<CollectionPeriodicalYear>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionTopic {
+    pub publication_id: i64,
+    pub publication_symbol: String,
+    pub publication_title: String,
+
+    pub topic: PublicationTopic,
+}
+
+// Where a `MepsDocumentId` can be read on installed publications
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CollectionDocumentLocation {
+    pub publication_id: i64,
+    pub publication_symbol: String,
+    pub language_index: i32,
+    pub meps_document_id: i32,
+    pub document_id: i32,
+}
+
+// Same document in another installed language, read through the
+// `_from` document commands with its publication symbol
+#[derive(Serialize, Deserialize)]
+pub struct CollectionDocumentTranslation {
+    pub location: CollectionDocumentLocation,
+    // Paragraph to scroll to on the translated document
+    pub paragraph_index: Option<i32>,
+}
+
+// Paragraph ordinal with its content in both languages, either missing
+// when a translation has no paragraph with that ordinal
+#[derive(Serialize, Deserialize)]
+pub struct CollectionAlignedParagraph {
+    pub paragraph_index: i32,
+    pub primary: Option<PublicationParagraph>,
+    pub secondary: Option<PublicationParagraph>,
+}
+
+// Set when both translations don't share the same paragraph ordinals
+#[derive(Serialize, Deserialize)]
+pub struct CollectionParagraphMismatch {
+    pub primary_count: usize,
+    pub secondary_count: usize,
+    pub missing_in_primary: Vec<i32>,
+    pub missing_in_secondary: Vec<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionBilingualDocument {
+    pub primary: CollectionDocumentLocation,
+    pub secondary: CollectionDocumentLocation,
+    pub paragraphs: Vec<CollectionAlignedParagraph>,
+    pub mismatch: Option<CollectionParagraphMismatch>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionExtract {
+    pub extract: PublicationExtract,
+    pub location: Option<CollectionDocumentLocation>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CollectionRelatedDocument {
+    pub related_document: RelatedDocument,
+    pub location: Option<CollectionDocumentLocation>,
+}
+
 impl Catalog {
     pub fn init<T: Into<PathBuf>>(location: T) -> Result<Self, Box<dyn std::error::Error>> {
         debug!(target: TARGET, "Initializing catalog...");
@@ -95,6 +363,10 @@ impl Catalog {
         }
 
         let db = Connection::open(location.join("collections.db"))?;
+        // WAL lets the read connections keep working while the writer
+        // is in the middle of an install.
+        db.pragma_update(None, "journal_mode", "WAL")?;
+        db.busy_timeout(Duration::from_secs(10))?;
 
         debug!(target: TARGET,  "initializing \"{}\" table...", "Publication".magenta());
         db.execute(
@@ -252,15 +524,17 @@ impl Catalog {
         debug!(target: TARGET, "Catalog initialized at {}!", location.display().to_string().green());
 
         Ok(Self {
+            readers: ConnectionPool::read_only(location.join("collections.db"), MAX_IDLE_READERS),
             pub_path: location,
-            catalog_db: db,
-            current_open: String::new(),
-            publication_cache: LruCache::new(NonZero::new(5).unwrap()),
+            writer: Mutex::new(db),
+            current_open: Mutex::new(None),
+            publication_cache: Mutex::new(LruCache::new(NonZero::new(5).unwrap())),
         })
     }
 
     pub fn update_metadata_for_publication(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         pub_manifest: &Manifest,
         first_dated_text_offset: Option<i32>,
@@ -268,7 +542,7 @@ impl Catalog {
         database_path: String,
         on_external_storage: Option<i32>,
     ) -> Result<i64, Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "UPDATE Publication SET
                 LanguageIndex = ?1,
                 PublicationType = ?2,
@@ -317,8 +591,8 @@ impl Catalog {
                 &pub_manifest.publication.year,
                 0, // Volume number
                 &pub_manifest.publication.issue_number,
-                first_dated_text_offset.unwrap_or(19691231),
-                last_dated_text_offset.unwrap_or(19691231),
+                first_dated_text_offset.unwrap_or(dates::NO_DATE_OFFSET),
+                last_dated_text_offset.unwrap_or(dates::NO_DATE_OFFSET),
                 &pub_manifest.publication.root_symbol,
                 &pub_manifest.publication.root_year,
                 &pub_manifest.publication.root_language,
@@ -338,7 +612,7 @@ impl Catalog {
             ],
         )?;
 
-        let pub_id = self.catalog_db.last_insert_rowid();
+        let pub_id = db.last_insert_rowid();
 
         debug!(target: TARGET, "Metadata updated on collection database for ID {}!", pub_id);
 
@@ -346,14 +620,15 @@ impl Catalog {
     }
 
     pub fn insert_metadata_for_publication(
-        &mut self,
+        &self,
+        db: &Connection,
         pub_manifest: &Manifest,
         first_dated_text_offset: Option<i32>,
         last_dated_text_offset: Option<i32>,
         database_path: String,
         on_external_storage: Option<i32>,
     ) -> Result<i64, Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "INSERT INTO Publication (
                 LanguageIndex,
                 PublicationType,
@@ -403,8 +678,8 @@ impl Catalog {
                 &pub_manifest.publication.year,
                 0, // Volume number
                 &pub_manifest.publication.issue_number,
-                first_dated_text_offset.unwrap_or(19691231),
-                last_dated_text_offset.unwrap_or(19691231),
+                first_dated_text_offset.unwrap_or(dates::NO_DATE_OFFSET),
+                last_dated_text_offset.unwrap_or(dates::NO_DATE_OFFSET),
 
                 &pub_manifest.publication.root_symbol,
                 &pub_manifest.publication.root_year,
@@ -428,7 +703,7 @@ impl Catalog {
             ]
         )?;
 
-        let pub_id = self.catalog_db.last_insert_rowid();
+        let pub_id = db.last_insert_rowid();
 
         debug!(target: TARGET, "Metadata inserted to collection database for ID {}!", pub_id);
 
@@ -436,11 +711,12 @@ impl Catalog {
     }
 
     pub fn delete_attribute_for_publication<'a>(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         attribute: &'a str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "DELETE FROM PublicationAttribute WHERE 
                 PublicationId=?1 AND
                 Attribute=?2",
@@ -453,11 +729,12 @@ impl Catalog {
     }
 
     pub fn insert_attribute_for_publication<'a>(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         attribute: &'a str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "INSERT INTO PublicationAttribute (
                 PublicationId,
                 Attribute
@@ -471,11 +748,12 @@ impl Catalog {
     }
 
     pub fn delete_issue_attribute_for_publication<'a>(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         attribute: &'a str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "DELETE FROM PublicationIssueAttribute WHERE
                 PublicationId=?1 AND
                 Attribute=?2",
@@ -488,11 +766,12 @@ impl Catalog {
     }
 
     pub fn insert_issue_attribute_for_publication<'a>(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         attribute: &'a str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "INSERT INTO PublicationIssueAttribute (
                 PublicationId,
                 Attribute
@@ -506,11 +785,12 @@ impl Catalog {
     }
 
     pub fn delete_issue_property_for_publication(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         property: &IssueProperties,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "DELETE FROM PublicationIssueProperty WHERE
                 PublicationId=?1 AND
                 Symbol=?2",
@@ -523,11 +803,12 @@ impl Catalog {
     }
 
     pub fn insert_issue_property_for_publication(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         property: &IssueProperties,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "INSERT INTO PublicationIssueProperty (
                 PublicationId,
                 Title,
@@ -552,12 +833,13 @@ impl Catalog {
     }
 
     pub fn delete_image_for_publication(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         image: &Image,
         path: String,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "DELETE FROM Image WHERE
                 PublicationId=?1 AND
                 Signature=?2",
@@ -570,12 +852,13 @@ impl Catalog {
     }
 
     pub fn insert_image_for_publication(
-        &mut self,
+        &self,
+        db: &Connection,
         id: i64,
         image: &Image,
         path: String,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.catalog_db.execute(
+        db.execute(
             "INSERT INTO Image (
                 PublicationId,
                 Type,
@@ -602,8 +885,9 @@ impl Catalog {
     }
 
     pub fn remove_indexed_dated_texts(
-        &mut self,
-        publication: &mut Publication,
+        &self,
+        db: &Connection,
+        publication: &Publication,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let dated_texts = publication.get_dated_texts()?;
 
@@ -621,7 +905,7 @@ impl Catalog {
                 document.class.to_string().yellow()
             );
 
-            self.catalog_db.execute(
+            db.execute(
                 "DELETE FROM DatedText WHERE
                     PublicationId=?1 AND
                     Start=?2 AND
@@ -640,8 +924,9 @@ impl Catalog {
     }
 
     fn index_dated_texts(
-        &mut self,
-        publication: &mut Publication,
+        &self,
+        db: &Connection,
+        publication: &Publication,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let dated_texts = publication.get_dated_texts()?;
 
@@ -659,7 +944,7 @@ impl Catalog {
                 document.class.to_string().yellow()
             );
 
-            self.catalog_db.execute(
+            db.execute(
                 "INSERT INTO DatedText (
                     PublicationId,
                     Start,
@@ -679,8 +964,9 @@ impl Catalog {
     }
 
     fn remove_indexed_documents(
-        &mut self,
-        publication: &mut Publication,
+        &self,
+        db: &Connection,
+        publication: &Publication,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let documents = publication.get_documents()?;
 
@@ -693,7 +979,7 @@ impl Catalog {
                 document.meps_language_id.to_string().yellow()
             );
 
-            self.catalog_db.execute(
+            db.execute(
                 "DELETE FROM Document WHERE
                     LanguageIndex=?1 AND
                     MepsDocumentId=?2 AND
@@ -710,8 +996,9 @@ impl Catalog {
     }
 
     fn index_documents(
-        &mut self,
-        publication: &mut Publication,
+        &self,
+        db: &Connection,
+        publication: &Publication,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let documents = publication.get_documents()?;
 
@@ -724,7 +1011,7 @@ impl Catalog {
                 document.meps_language_id.to_string().yellow()
             );
 
-            self.catalog_db.execute(
+            db.execute(
                 "INSERT INTO Document (
                     LanguageIndex,
                     MepsDocumentId,
@@ -742,7 +1029,7 @@ impl Catalog {
     }
 
     pub fn install_jwpub_from_archive(
-        &mut self,
+        &self,
         file: Vec<u8>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         info!(target: TARGET, "Installing from archive data...");
@@ -753,6 +1040,10 @@ impl Catalog {
         let manifest = get_metadata_from_archive(&mut package)?;
         let pub_pathname = manifest.name.replace(".jwpub", "");
 
+        // Held until the catalog is updated, so installs of the same
+        // publication can't write over each other's files
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+
         debug!(target: TARGET, "Checking if JWPUB doesn't match with any publication installed...");
         let mut existing_id = None;
         if let Some(publication_data) =
@@ -775,6 +1066,12 @@ impl Catalog {
             }
         }
 
+        // Drops the cached connections to the files about to be replaced
+        self.publication_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop(&pub_pathname);
+
         debug!(target: TARGET, "Configuring directory...");
         let location = self.pub_path.join(&pub_pathname);
         if !location.exists() {
@@ -794,6 +1091,7 @@ impl Catalog {
         serde_json::to_writer_pretty(manifest_file, &manifest)?;
 
         info!(target: TARGET, "Indexing data to catalog...");
+        let tx = writer.transaction()?;
         let mut tmp_publication =
             Publication::from_database(location.join(&manifest.publication.file_name), -1)?;
         let mut first_dated_text_offset = None;
@@ -807,6 +1105,7 @@ impl Catalog {
 
         let publication_id = if let Some(id) = existing_id {
             self.update_metadata_for_publication(
+                &tx,
                 id as i64,
                 &manifest,
                 first_dated_text_offset,
@@ -821,6 +1120,7 @@ impl Catalog {
             id as i64
         } else {
             self.insert_metadata_for_publication(
+                &tx,
                 &manifest,
                 first_dated_text_offset,
                 last_dated_text_offset,
@@ -838,9 +1138,13 @@ impl Catalog {
         if manifest.publication.attributes.len() > 0 {
             for attribute in manifest.publication.attributes.iter() {
                 if existing_id.is_some() {
-                    self.delete_attribute_for_publication(tmp_publication.catalog_id, attribute)?;
+                    self.delete_attribute_for_publication(
+                        &tx,
+                        tmp_publication.catalog_id,
+                        attribute,
+                    )?;
                 }
-                self.insert_attribute_for_publication(tmp_publication.catalog_id, attribute)?;
+                self.insert_attribute_for_publication(&tx, tmp_publication.catalog_id, attribute)?;
             }
         }
 
@@ -848,11 +1152,13 @@ impl Catalog {
             for issue_attribute in manifest.publication.issue_attributes.iter() {
                 if existing_id.is_some() {
                     self.delete_issue_attribute_for_publication(
+                        &tx,
                         tmp_publication.catalog_id,
                         issue_attribute,
                     )?;
                 }
                 self.insert_issue_attribute_for_publication(
+                    &tx,
                     tmp_publication.catalog_id,
                     issue_attribute,
                 )?;
@@ -862,11 +1168,13 @@ impl Catalog {
         if !manifest.publication.issue_properties.symbol.is_empty() {
             if existing_id.is_some() {
                 self.delete_issue_property_for_publication(
+                    &tx,
                     tmp_publication.catalog_id,
                     &manifest.publication.issue_properties,
                 )?;
             }
             self.insert_issue_property_for_publication(
+                &tx,
                 tmp_publication.catalog_id,
                 &manifest.publication.issue_properties,
             )?;
@@ -876,12 +1184,14 @@ impl Catalog {
             for image in manifest.publication.images.iter() {
                 if existing_id.is_some() {
                     self.delete_image_for_publication(
+                        &tx,
                         tmp_publication.catalog_id,
                         image,
                         location.join(&image.file_name).to_str().unwrap().to_owned(),
                     )?;
                 }
                 self.insert_image_for_publication(
+                    &tx,
                     tmp_publication.catalog_id,
                     image,
                     location.join(&image.file_name).to_str().unwrap().to_owned(),
@@ -891,15 +1201,24 @@ impl Catalog {
 
         if dated_texts.len() > 0 {
             if existing_id.is_some() {
-                self.remove_indexed_dated_texts(&mut tmp_publication)?;
+                self.remove_indexed_dated_texts(&tx, &tmp_publication)?;
             }
-            self.index_dated_texts(&mut tmp_publication)?;
+            self.index_dated_texts(&tx, &tmp_publication)?;
         }
 
         if existing_id.is_some() {
-            self.remove_indexed_documents(&mut tmp_publication)?;
+            self.remove_indexed_documents(&tx, &tmp_publication)?;
         }
-        self.index_documents(&mut tmp_publication)?;
+        self.index_documents(&tx, &tmp_publication)?;
+
+        tx.commit()?;
+
+        // A reader may have opened the publication again while its files
+        // were being replaced, with metadata of the previous version
+        self.publication_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop(&pub_pathname);
 
         info!(
             target: TARGET,
@@ -912,7 +1231,7 @@ impl Catalog {
     }
 
     pub fn install_jwpub_file<T: Into<PathBuf>>(
-        &mut self,
+        &self,
         file_path: T,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let file_path: PathBuf = file_path.into();
@@ -927,6 +1246,10 @@ impl Catalog {
         let manifest = get_metadata_from_archive(&mut package)?;
         let pub_pathname = manifest.name.replace(".jwpub", "");
 
+        // Held until the catalog is updated, so installs of the same
+        // publication can't write over each other's files
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+
         debug!(target: TARGET, "Checking if JWPUB doesn't match with any publication installed...");
         let mut existing_id = None;
         if let Some(publication_data) =
@@ -949,8 +1272,14 @@ impl Catalog {
             }
         }
 
+        // Drops the cached connections to the files about to be replaced
+        self.publication_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop(&pub_pathname);
+
         debug!(target: TARGET, "Configuring directory...");
-        let location = self.pub_path.join(pub_pathname);
+        let location = self.pub_path.join(&pub_pathname);
         if !location.exists() {
             fs_extra::dir::create_all(&location, false)?;
         }
@@ -968,6 +1297,7 @@ impl Catalog {
         serde_json::to_writer_pretty(manifest_file, &manifest)?;
 
         info!(target: TARGET, "Indexing data to catalog...");
+        let tx = writer.transaction()?;
         let mut tmp_publication =
             Publication::from_database(location.join(&manifest.publication.file_name), -1)?;
         let mut first_dated_text_offset = None;
@@ -981,6 +1311,7 @@ impl Catalog {
 
         let publication_id = if let Some(id) = existing_id {
             self.update_metadata_for_publication(
+                &tx,
                 id as i64,
                 &manifest,
                 first_dated_text_offset,
@@ -995,6 +1326,7 @@ impl Catalog {
             id as i64
         } else {
             self.insert_metadata_for_publication(
+                &tx,
                 &manifest,
                 first_dated_text_offset,
                 last_dated_text_offset,
@@ -1012,9 +1344,13 @@ impl Catalog {
         if manifest.publication.attributes.len() > 0 {
             for attribute in manifest.publication.attributes.iter() {
                 if existing_id.is_some() {
-                    self.delete_attribute_for_publication(tmp_publication.catalog_id, attribute)?;
+                    self.delete_attribute_for_publication(
+                        &tx,
+                        tmp_publication.catalog_id,
+                        attribute,
+                    )?;
                 }
-                self.insert_attribute_for_publication(tmp_publication.catalog_id, attribute)?;
+                self.insert_attribute_for_publication(&tx, tmp_publication.catalog_id, attribute)?;
             }
         }
 
@@ -1022,11 +1358,13 @@ impl Catalog {
             for issue_attribute in manifest.publication.issue_attributes.iter() {
                 if existing_id.is_some() {
                     self.delete_issue_attribute_for_publication(
+                        &tx,
                         tmp_publication.catalog_id,
                         issue_attribute,
                     )?;
                 }
                 self.insert_issue_attribute_for_publication(
+                    &tx,
                     tmp_publication.catalog_id,
                     issue_attribute,
                 )?;
@@ -1036,11 +1374,13 @@ impl Catalog {
         if !manifest.publication.issue_properties.symbol.is_empty() {
             if existing_id.is_some() {
                 self.delete_issue_property_for_publication(
+                    &tx,
                     tmp_publication.catalog_id,
                     &manifest.publication.issue_properties,
                 )?;
             }
             self.insert_issue_property_for_publication(
+                &tx,
                 tmp_publication.catalog_id,
                 &manifest.publication.issue_properties,
             )?;
@@ -1050,12 +1390,14 @@ impl Catalog {
             for image in manifest.publication.images.iter() {
                 if existing_id.is_some() {
                     self.delete_image_for_publication(
+                        &tx,
                         tmp_publication.catalog_id,
                         image,
                         location.join(&image.file_name).to_str().unwrap().to_owned(),
                     )?;
                 }
                 self.insert_image_for_publication(
+                    &tx,
                     tmp_publication.catalog_id,
                     image,
                     location.join(&image.file_name).to_str().unwrap().to_owned(),
@@ -1065,15 +1407,24 @@ impl Catalog {
 
         if dated_texts.len() > 0 {
             if existing_id.is_some() {
-                self.remove_indexed_dated_texts(&mut tmp_publication)?;
+                self.remove_indexed_dated_texts(&tx, &tmp_publication)?;
             }
-            self.index_dated_texts(&mut tmp_publication)?;
+            self.index_dated_texts(&tx, &tmp_publication)?;
         }
 
         if existing_id.is_some() {
-            self.remove_indexed_documents(&mut tmp_publication)?;
+            self.remove_indexed_documents(&tx, &tmp_publication)?;
         }
-        self.index_documents(&mut tmp_publication)?;
+        self.index_documents(&tx, &tmp_publication)?;
+
+        tx.commit()?;
+
+        // A reader may have opened the publication again while its files
+        // were being replaced, with metadata of the previous version
+        self.publication_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop(&pub_pathname);
 
         info!(
             target: TARGET,
@@ -1090,7 +1441,8 @@ impl Catalog {
         image_type: &'a str,
         publication_id: i64,
     ) -> Result<Vec<CollectionImage>, Box<dyn std::error::Error>> {
-        let mut stmt = self.catalog_db.prepare(
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
             "SELECT
                     ImageId,
                     PublicationId,
@@ -1127,7 +1479,8 @@ impl Catalog {
         &self,
         publication_type: &str,
     ) -> Result<i32, Box<dyn std::error::Error>> {
-        let mut stmt = self.catalog_db.prepare(
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
             "SELECT
                 COUNT(PublicationId)
             FROM Publication WHERE PublicationType=?1",
@@ -1140,7 +1493,8 @@ impl Catalog {
         &self,
         publication_type: &str,
     ) -> Result<Vec<CollectionPublication>, Box<dyn std::error::Error>> {
-        let mut stmt = self.catalog_db.prepare(
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
             "SELECT
                 PublicationId,
                 LanguageIndex,
@@ -1183,49 +1537,75 @@ impl Catalog {
         let mut rows = stmt.query([publication_type])?;
         let mut pub_collection = Vec::new();
         while let Some(row) = rows.next()? {
-            pub_collection.push(CollectionPublication {
-                id: row.get(0)?,
-                language_index: row.get(1)?,
-                publication_type: row.get(2)?,
-                publication_category_symbol: row.get(3)?,
-                title: row.get(4)?,
-                short_title: row.get(5)?,
-                display_title: row.get(6)?,
-                symbol: row.get(7)?,
-                unique_english_symbol: row.get(8)?,
-                year: row.get(9)?,
-                volume_number: row.get(10)?,
-                issue_tag_number: row.get(11)?,
-                first_dated_text_date_offset: row.get(12)?,
-                last_dated_text_date_offset: row.get(13)?,
-                root_symbol: row.get(14)?,
-                root_year: row.get(15)?,
-                root_meps_language_index: row.get(16)?,
-                version_number: row.get(17)?,
-                schema_version_number: row.get(18)?,
-                hash: row.get(19)?,
-                timestamp: row.get(20)?,
-                jwpub: row.get(21)?,
-                database_path: row.get(22)?,
-                on_external_storage: row.get(23)?,
-                undated_reference_title: row.get(24)?,
-                expanded_size: row.get(25)?,
-                min_platform_version: row.get(26)?,
-                key_symbol: row.get(27)?,
-                meps_build_number: row.get(28)?,
-            })
+            pub_collection.push(CollectionPublication::from_row(row)?)
         }
+        // Latest issues first, publications without an issue keep their order
+        pub_collection.sort_by(|a, b| b.issue_date.cmp(&a.issue_date));
 
         debug!(target: TARGET, "Getting list for \"{}\"... (Length: {} items)", publication_type.blue(), pub_collection.len().to_string().yellow());
 
         Ok(pub_collection)
     }
 
+    pub fn get_publications(
+        &self,
+    ) -> Result<Vec<CollectionPublication>, Box<dyn std::error::Error>> {
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+                PublicationId,
+                LanguageIndex,
+                PublicationType,
+                PublicationCategorySymbol,
+                
+                Title,
+                ShortTitle,
+                DisplayTitle,
+
+                Symbol,
+                UniqueEnglishSymbol,
+                Year,
+                VolumeNumber,
+                IssueTagNumber,
+                FirstDatedTextDateOffset,
+                LastDatedTextDateOffset,
+                
+                RootSymbol,
+                RootYear,
+                RootMepsLanguageIndex,
+                
+                VersionNumber,
+                SchemaVersionNumber,
+                Hash,
+                Timestamp,
+                
+                JwPub,
+                DatabasePath,
+                
+                OnExternalStorage,
+                UndatedReferenceTitle,
+                
+                ExpandedSize,
+                MinPlatformVersion,
+                KeySymbol,
+                MepsBuildNumber
+            FROM Publication",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut pub_collection = Vec::new();
+        while let Some(row) = rows.next()? {
+            pub_collection.push(CollectionPublication::from_row(row)?)
+        }
+
+        Ok(pub_collection)
+    }
+
     pub fn get_publication_collection_meta(
         &self,
         filename_symbol: &str,
     ) -> Result<Option<CollectionPublication>, Box<dyn std::error::Error>> {
-        let mut stmt = self.catalog_db.prepare(
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
             "SELECT
                 PublicationId,
                 LanguageIndex,
@@ -1267,71 +1647,974 @@ impl Catalog {
         )?;
         let mut rows = stmt.query([format!("{}.jwpub", filename_symbol)])?;
         if let Some(row) = rows.next()? {
-            return Ok(Some(CollectionPublication {
+            return Ok(Some(CollectionPublication::from_row(row)?));
+        }
+
+        Ok(None)
+    }
+
+    pub fn get_images_for(
+        &self,
+        publication_id: i64,
+    ) -> Result<Vec<CollectionImage>, Box<dyn std::error::Error>> {
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+                    ImageId,
+                    PublicationId,
+                    Type,
+                    Attribute,
+                    Path,
+                    Width,
+                    Height,
+                    Signature
+                FROM Image
+            WHERE PublicationId =?1",
+        )?;
+        let mut rows = stmt.query(params![publication_id])?;
+        let mut images = Vec::new();
+        while let Some(row) = rows.next()? {
+            images.push(CollectionImage {
                 id: row.get(0)?,
-                language_index: row.get(1)?,
-                publication_type: row.get(2)?,
-                publication_category_symbol: row.get(3)?,
-                title: row.get(4)?,
-                short_title: row.get(5)?,
-                display_title: row.get(6)?,
-                symbol: row.get(7)?,
-                unique_english_symbol: row.get(8)?,
-                year: row.get(9)?,
-                volume_number: row.get(10)?,
-                issue_tag_number: row.get(11)?,
-                first_dated_text_date_offset: row.get(12)?,
-                last_dated_text_date_offset: row.get(13)?,
-                root_symbol: row.get(14)?,
-                root_year: row.get(15)?,
-                root_meps_language_index: row.get(16)?,
-                version_number: row.get(17)?,
-                schema_version_number: row.get(18)?,
-                hash: row.get(19)?,
-                timestamp: row.get(20)?,
-                jwpub: row.get(21)?,
-                database_path: row.get(22)?,
-                on_external_storage: row.get(23)?,
-                undated_reference_title: row.get(24)?,
-                expanded_size: row.get(25)?,
-                min_platform_version: row.get(26)?,
-                key_symbol: row.get(27)?,
-                meps_build_number: row.get(28)?,
+                publication_id: row.get(1)?,
+                image_type: row.get(2)?,
+                attribute: row.get(3)?,
+                path: row.get(4)?,
+                width: row.get(5)?,
+                height: row.get(6)?,
+                signature: row.get(7)?,
+            });
+        }
+
+        Ok(images)
+    }
+
+    pub fn get_issue_attributes_for(
+        &self,
+        publication_id: i64,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+                Attribute
+            FROM PublicationIssueAttribute WHERE PublicationId=?1",
+        )?;
+        let mut rows = stmt.query(params![publication_id])?;
+        let mut attributes = Vec::new();
+        while let Some(row) = rows.next()? {
+            attributes.push(row.get(0)?);
+        }
+
+        Ok(attributes)
+    }
+
+    pub fn get_issue_property_for(
+        &self,
+        publication_id: i64,
+    ) -> Result<Option<CollectionIssueProperty>, Box<dyn std::error::Error>> {
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+                Title,
+                UndatedTitle,
+                CoverTitle,
+                Symbol,
+                UndatedSymbol
+            FROM PublicationIssueProperty WHERE PublicationId=?1",
+        )?;
+        let mut rows = stmt.query(params![publication_id])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(CollectionIssueProperty {
+                title: row.get(0)?,
+                undated_title: row.get(1)?,
+                cover_title: row.get(2)?,
+                symbol: row.get(3)?,
+                undated_symbol: row.get(4)?,
             }));
         }
 
         Ok(None)
     }
 
-    pub fn get_current_publication(&mut self) -> Option<&mut Publication> {
-        self.publication_cache.get_mut(&self.current_open)
+    /// Lists periodicals of `publication_type` grouped by root symbol, year
+    /// and issue, newest years and issues first. Editions of the same issue
+    /// are grouped by their `IssueTagNumber`.
+    pub fn get_periodicals_from_type(
+        &self,
+        publication_type: &str,
+    ) -> Result<Vec<CollectionPeriodicalGroup>, Box<dyn std::error::Error>> {
+        // One row per publication image, with the first issue property of
+        // the publication like `get_issue_property_for`. Issue attributes
+        // are concatenated with the unit separator
+        let db = self.readers.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+                Publication.PublicationId,
+                Publication.LanguageIndex,
+                Publication.PublicationType,
+                Publication.PublicationCategorySymbol,
+
+                Publication.Title,
+                Publication.ShortTitle,
+                Publication.DisplayTitle,
+
+                Publication.Symbol,
+                Publication.UniqueEnglishSymbol,
+                Publication.Year,
+                Publication.VolumeNumber,
+                Publication.IssueTagNumber,
+                Publication.FirstDatedTextDateOffset,
+                Publication.LastDatedTextDateOffset,
+
+                Publication.RootSymbol,
+                Publication.RootYear,
+                Publication.RootMepsLanguageIndex,
+
+                Publication.VersionNumber,
+                Publication.SchemaVersionNumber,
+                Publication.Hash,
+                Publication.Timestamp,
+
+                Publication.JwPub,
+                Publication.DatabasePath,
+
+                Publication.OnExternalStorage,
+                Publication.UndatedReferenceTitle,
+
+                Publication.ExpandedSize,
+                Publication.MinPlatformVersion,
+                Publication.KeySymbol,
+                Publication.MepsBuildNumber,
+
+                PublicationIssueProperty.Title,
+                PublicationIssueProperty.UndatedTitle,
+                PublicationIssueProperty.CoverTitle,
+                PublicationIssueProperty.Symbol,
+                PublicationIssueProperty.UndatedSymbol,
+                (SELECT group_concat(Attribute, char(31))
+                    FROM PublicationIssueAttribute
+                    WHERE PublicationIssueAttribute.PublicationId = Publication.PublicationId),
+
+                Image.ImageId,
+                Image.Type,
+                Image.Attribute,
+                Image.Path,
+                Image.Width,
+                Image.Height,
+                Image.Signature
+            FROM Publication
+            LEFT JOIN PublicationIssueProperty ON PublicationIssueProperty.PublicationIssuePropertyId = (
+                SELECT MIN(PublicationIssuePropertyId) FROM PublicationIssueProperty
+                WHERE PublicationIssueProperty.PublicationId = Publication.PublicationId
+            )
+            LEFT JOIN Image ON Image.PublicationId = Publication.PublicationId
+            WHERE Publication.PublicationType = ?1
+            ORDER BY Publication.PublicationId, Image.ImageId",
+        )?;
+        let mut rows = stmt.query([publication_type])?;
+
+        let mut editions: Vec<CollectionPeriodicalEditionItem> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let publication_id: i32 = row.get(0)?;
+            if editions
+                .last()
+                .map_or(true, |edition| edition.publication.id != publication_id)
+            {
+                let issue_property = match row.get::<_, Option<String>>(29)? {
+                    Some(title) => Some(CollectionIssueProperty {
+                        title,
+                        undated_title: row.get(30)?,
+                        cover_title: row.get(31)?,
+                        symbol: row.get(32)?,
+                        undated_symbol: row.get(33)?,
+                    }),
+                    None => None,
+                };
+                let issue_attributes: Vec<String> = row
+                    .get::<_, Option<String>>(34)?
+                    .map(|attributes| {
+                        attributes
+                            .split('\u{1f}')
+                            .map(|attribute| attribute.to_owned())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                editions.push(CollectionPeriodicalEditionItem {
+                    publication: CollectionPublication::from_row(row)?,
+                    issue_property,
+                    edition: PeriodicalEdition::from_issue_attributes(&issue_attributes),
+                    issue_attributes,
+                    images: Vec::new(),
+                });
+            }
+
+            if let Some(image_id) = row.get::<_, Option<i32>>(35)? {
+                let edition = editions.last_mut().ok_or("Image row without publication")?;
+                edition.images.push(CollectionImage {
+                    id: image_id,
+                    publication_id,
+                    image_type: row.get(36)?,
+                    attribute: row.get(37)?,
+                    path: row.get(38)?,
+                    width: row.get(39)?,
+                    height: row.get(40)?,
+                    signature: row.get(41)?,
+                });
+            }
+        }
+
+        let mut groups: BTreeMap<
+            String,
+            BTreeMap<i32, BTreeMap<Option<i32>, Vec<CollectionPeriodicalEditionItem>>>,
+        > = BTreeMap::new();
+        for edition in editions {
+            groups
+                .entry(edition.publication.root_symbol.clone())
+                .or_default()
+                .entry(edition.publication.year)
+                .or_default()
+                .entry(edition.publication.issue_tag_number)
+                .or_default()
+                .push(edition);
+        }
+
+        debug!(target: TARGET, "Grouping periodicals for \"{}\"... (Groups: {})", publication_type.blue(), groups.len().to_string().yellow());
+
+        Ok(groups
+            .into_iter()
+            .map(|(root_symbol, years)| CollectionPeriodicalGroup {
+                root_symbol,
+                years: years
+                    .into_iter()
+                    .rev()
+                    .map(|(year, issues)| CollectionPeriodicalYear {
+                        year,
+                        issues: issues
+                            .into_iter()
+                            .rev()
+                            .map(|(issue_tag_number, mut editions)| {
+                                editions.sort_by_key(|edition| edition.edition as u8);
+                                let issue_period =
+                                    issue_tag_number.and_then(IssuePeriod::from_issue_tag);
+                                CollectionPeriodicalIssue {
+                                    issue_tag_number,
+                                    issue_period,
+                                    issue_date: issue_period.and_then(|period| period.start_date()),
+                                    editions,
+                                }
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    pub fn get_topic_index_of(
+        &self,
+        filename_symbol: &str,
+        query: &str,
+    ) -> Result<Vec<PublicationTopic>, Box<dyn std::error::Error>> {
+        self.get_publication(filename_symbol)?
+            .get_topic_index(query)
     }
 
-    pub fn open_publication_connection(
-        &mut self,
-        filename_symbol: String,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(_publication) = self.publication_cache.get(&filename_symbol) {
-            debug!(target: TARGET, "Reopening connection with \"{}\"...", filename_symbol);
-            self.current_open = filename_symbol.clone();
-            return Ok(());
+    /// Searches topics matching `query` on every installed publication
+    /// that ships a topic index.
+    pub fn search_topics(
+        &self,
+        query: &str,
+    ) -> Result<Vec<CollectionTopic>, Box<dyn std::error::Error>> {
+        let mut topics = Vec::new();
+        if query.trim().is_empty() {
+            return Ok(topics);
+        }
+
+        for publication_metadata in self.get_publications()? {
+            let publication_symbol = publication_metadata.jwpub.replace(".jwpub", "");
+            // A broken publication doesn't fail the whole search
+            let index = match self
+                .get_publication(&publication_symbol)
+                .and_then(|publication| publication.get_topic_index(query))
+            {
+                Ok(index) => index,
+                Err(err) => {
+                    warn!(target: TARGET, "Skipping topics of \"{}\": {}", publication_symbol, err);
+                    continue;
+                }
+            };
+
+            for topic in index {
+                topics.push(CollectionTopic {
+                    publication_id: publication_metadata.id as i64,
+                    publication_symbol: publication_symbol.clone(),
+                    publication_title: publication_metadata.title.clone(),
+                    topic,
+                });
+            }
         }
 
-        if let Some(publication_metadata) =
-            self.get_publication_collection_meta(&filename_symbol)?
+        debug!(target: TARGET, "Searching topics for \"{}\"... (Length: {} items)", query.blue(), topics.len().to_string().yellow());
+
+        Ok(topics)
+    }
+
+    pub fn get_document_media_of(
+        &self,
+        filename_symbol: &str,
+        document_id: Option<i32>,
+    ) -> Result<Vec<DocumentMedia>, Box<dyn std::error::Error>> {
+        let media = self
+            .get_publication(filename_symbol)?
+            .get_document_media(document_id)?;
+
+        debug!(target: TARGET, "Getting media inventory on \"{}\"... (Length: {} items)", filename_symbol.green(), media.len().to_string().yellow());
+
+        Ok(media)
+    }
+
+    pub fn get_paragraph_video_segments_of(
+        &self,
+        filename_symbol: &str,
+        document_id: i32,
+        paragraph_index: Option<i32>,
+    ) -> Result<Vec<VideoSegment>, Box<dyn std::error::Error>> {
+        self.get_publication(filename_symbol)?
+            .get_paragraph_video_segments(document_id, paragraph_index)
+    }
+
+    pub fn get_verse_video_segments_of(
+        &self,
+        filename_symbol: &str,
+        bible_verse_id: i32,
+    ) -> Result<Vec<VideoSegment>, Box<dyn std::error::Error>> {
+        self.get_publication(filename_symbol)?
+            .get_verse_video_segments(bible_verse_id)
+    }
+
+    pub fn get_extract_video_segments_of(
+        &self,
+        filename_symbol: &str,
+        extract_id: i32,
+    ) -> Result<Vec<ExtractVideoSegment>, Box<dyn std::error::Error>> {
+        self.get_publication(filename_symbol)?
+            .get_extract_video_segments(extract_id)
+    }
+
+    /// Resolves a `MepsDocumentId` to the installed publications containing
+    /// it, preferring `language_index` when given.
+    pub fn locate_meps_document(
+        &self,
+        meps_document_id: i32,
+        language_index: Option<i32>,
+    ) -> Result<Vec<CollectionDocumentLocation>, Box<dyn std::error::Error>> {
+        Ok(self
+            .locate_meps_documents(&[(meps_document_id, language_index)])?
+            .remove(&(meps_document_id, language_index))
+            .unwrap_or_default())
+    }
+
+    /// Same as `locate_meps_document` for several documents at once, keyed
+    /// by `(meps_document_id, language_index)`. Each candidate publication is
+    /// opened once, through the publication cache.
+    pub fn locate_meps_documents(
+        &self,
+        documents: &[(i32, Option<i32>)],
+    ) -> Result<
+        HashMap<(i32, Option<i32>), Vec<CollectionDocumentLocation>>,
+        Box<dyn std::error::Error>,
+    > {
+        let mut candidates = Vec::new();
         {
-            debug!(target: TARGET, "Opening connection with \"{}\"...", filename_symbol);
-            let publication = Publication::from_database(
-                PathBuf::from(publication_metadata.database_path),
-                publication_metadata.id as i64,
+            let db = self.readers.get()?;
+            let mut stmt = db.prepare(
+                "SELECT
+                    Publication.PublicationId,
+                    Publication.JwPub,
+                    Document.LanguageIndex
+                FROM Document
+                INNER JOIN Publication ON Publication.PublicationId = Document.PublicationId
+                WHERE Document.MepsDocumentId = ?1 AND
+                    (?2 IS NULL OR Document.LanguageIndex = ?2)
+                ORDER BY Publication.Timestamp DESC",
+            )?;
+
+            let mut seen = HashSet::new();
+            for &(meps_document_id, language_index) in documents {
+                if !seen.insert((meps_document_id, language_index)) {
+                    continue;
+                }
+
+                let mut rows = stmt.query(params![meps_document_id, language_index])?;
+                while let Some(row) = rows.next()? {
+                    candidates.push((
+                        (meps_document_id, language_index),
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?.replace(".jwpub", ""),
+                        row.get::<_, i32>(2)?,
+                    ));
+                }
+            }
+        }
+
+        let mut publications: HashMap<String, Arc<Publication>> = HashMap::new();
+        let mut locations: HashMap<(i32, Option<i32>), Vec<CollectionDocumentLocation>> =
+            HashMap::new();
+        for (key, publication_id, publication_symbol, language_index) in candidates {
+            let publication = match publications.get(&publication_symbol) {
+                Some(publication) => publication.clone(),
+                None => {
+                    let publication = self.get_publication(&publication_symbol)?;
+                    publications.insert(publication_symbol.clone(), publication.clone());
+                    publication
+                }
+            };
+
+            if let Some(document_id) = publication.get_document_id_by_meps_id(key.0)? {
+                locations
+                    .entry(key)
+                    .or_default()
+                    .push(CollectionDocumentLocation {
+                        publication_id,
+                        publication_symbol,
+                        language_index,
+                        meps_document_id: key.0,
+                        document_id,
+                    });
+            }
+        }
+
+        Ok(locations)
+    }
+
+    /// Lists the installed languages of a document, one location per
+    /// language (its own included), from the latest publication of each.
+    pub fn get_document_languages_of(
+        &self,
+        filename_symbol: &str,
+        document_id: i32,
+    ) -> Result<Vec<CollectionDocumentLocation>, Box<dyn std::error::Error>> {
+        let document = self
+            .get_publication(filename_symbol)?
+            .get_document_by_id(document_id)?
+            .ok_or("Document not found in publication")?;
+
+        // Locations come latest publication first
+        let mut languages = HashSet::new();
+        let mut locations = self.locate_meps_document(document.meps_document_id, None)?;
+        locations.retain(|location| languages.insert(location.language_index));
+
+        Ok(locations)
+    }
+
+    /// Resolves the same document of `filename_symbol` in `language_index`,
+    /// keeping the reader on `paragraph_index`. Nothing is opened, the
+    /// translation is read through its publication symbol.
+    pub fn switch_document_language(
+        &self,
+        filename_symbol: &str,
+        document_id: i32,
+        language_index: i32,
+        paragraph_index: Option<i32>,
+    ) -> Result<Option<CollectionDocumentTranslation>, Box<dyn std::error::Error>> {
+        let document = self
+            .get_publication(filename_symbol)?
+            .get_document_by_id(document_id)?
+            .ok_or("Document not found in publication")?;
+
+        let Some(location) = self
+            .locate_meps_document(document.meps_document_id, Some(language_index))?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+
+        // Translations share their paragraphs, but a shorter one is kept on
+        // its last paragraph
+        let paragraph_count = self
+            .get_publication(&location.publication_symbol)?
+            .get_document_by_id(location.document_id)?
+            .map(|translated| translated.paragraph_count)
+            .unwrap_or(0);
+        let paragraph_index = paragraph_index.map(|paragraph_index| match paragraph_count {
+            0 => paragraph_index,
+            _ => paragraph_index.clamp(0, paragraph_count - 1),
+        });
+
+        debug!(target: TARGET, "Document {} in language {} found on \"{}\".", document.meps_document_id.to_string().yellow(), language_index.to_string().yellow(), location.publication_symbol.green());
+
+        Ok(Some(CollectionDocumentTranslation {
+            location,
+            paragraph_index,
+        }))
+    }
+
+    /// Aligns a document of `filename_symbol` with its translation in
+    /// `language_index` by paragraph ordinal, for side-by-side reading.
+    /// Returns `None` when the translation isn't installed.
+    pub fn get_bilingual_document(
+        &self,
+        filename_symbol: &str,
+        document_id: i32,
+        language_index: i32,
+    ) -> Result<Option<CollectionBilingualDocument>, Box<dyn std::error::Error>> {
+        let publication = self.get_publication(filename_symbol)?;
+        let document = publication
+            .get_document_by_id(document_id)?
+            .ok_or("Document not found in publication")?;
+
+        let Some(secondary) = self
+            .locate_meps_document(document.meps_document_id, Some(language_index))?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+
+        let primary = CollectionDocumentLocation {
+            publication_id: publication.catalog_id,
+            publication_symbol: filename_symbol.to_owned(),
+            language_index: document.meps_language_id,
+            meps_document_id: document.meps_document_id,
+            document_id,
+        };
+
+        let primary_paragraphs = publication.get_document_paragraphs(document_id)?;
+        let secondary_paragraphs = self
+            .get_publication(&secondary.publication_symbol)?
+            .get_document_paragraphs(secondary.document_id)?;
+        let primary_count = primary_paragraphs.len();
+        let secondary_count = secondary_paragraphs.len();
+
+        let mut aligned: BTreeMap<i32, CollectionAlignedParagraph> = BTreeMap::new();
+        for paragraph in primary_paragraphs {
+            aligned.insert(
+                paragraph.paragraph_index,
+                CollectionAlignedParagraph {
+                    paragraph_index: paragraph.paragraph_index,
+                    primary: Some(paragraph),
+                    secondary: None,
+                },
+            );
+        }
+        for paragraph in secondary_paragraphs {
+            let paragraph_index = paragraph.paragraph_index;
+            aligned
+                .entry(paragraph_index)
+                .or_insert_with(|| CollectionAlignedParagraph {
+                    paragraph_index,
+                    primary: None,
+                    secondary: None,
+                })
+                .secondary = Some(paragraph);
+        }
+        let paragraphs: Vec<CollectionAlignedParagraph> = aligned.into_values().collect();
+
+        let missing_in_primary: Vec<i32> = paragraphs
+            .iter()
+            .filter(|paragraph| paragraph.primary.is_none())
+            .map(|paragraph| paragraph.paragraph_index)
+            .collect();
+        let missing_in_secondary: Vec<i32> = paragraphs
+            .iter()
+            .filter(|paragraph| paragraph.secondary.is_none())
+            .map(|paragraph| paragraph.paragraph_index)
+            .collect();
+
+        let mismatch = match missing_in_primary.is_empty() && missing_in_secondary.is_empty() {
+            true => None,
+            false => {
+                warn!(target: TARGET, "Paragraphs of document {} differ between languages {} ({}) and {} ({}).", document.meps_document_id, primary.language_index, primary_count, secondary.language_index, secondary_count);
+                Some(CollectionParagraphMismatch {
+                    primary_count,
+                    secondary_count,
+                    missing_in_primary,
+                    missing_in_secondary,
+                })
+            }
+        };
+
+        Ok(Some(CollectionBilingualDocument {
+            primary,
+            secondary,
+            paragraphs,
+            mismatch,
+        }))
+    }
+
+    pub fn get_document_extracts_of(
+        &self,
+        filename_symbol: &str,
+        document_id: i32,
+    ) -> Result<Vec<CollectionExtract>, Box<dyn std::error::Error>> {
+        let extracts = self
+            .get_publication(filename_symbol)?
+            .get_document_extracts(document_id)?;
+
+        let extract_language = |extract: &PublicationExtract| {
+            extract
+                .source
+                .as_ref()
+                .map(|source| source.meps_language_index)
+        };
+        let locations = self.locate_meps_documents(
+            &extracts
+                .iter()
+                .filter_map(|extract| {
+                    Some((extract.ref_meps_document_id?, extract_language(extract)))
+                })
+                .collect::<Vec<_>>(),
+        )?;
+
+        let mut collection_extracts = Vec::new();
+        for extract in extracts {
+            let location = extract.ref_meps_document_id.and_then(|meps_document_id| {
+                locations
+                    .get(&(meps_document_id, extract_language(&extract)))
+                    .and_then(|locations| locations.first().cloned())
+            });
+
+            collection_extracts.push(CollectionExtract { extract, location });
+        }
+
+        debug!(target: TARGET, "Getting extracts for document {} on \"{}\"... (Length: {} items)", document_id.to_string().yellow(), filename_symbol.green(), collection_extracts.len().to_string().yellow());
+
+        Ok(collection_extracts)
+    }
+
+    pub fn get_related_documents_of(
+        &self,
+        filename_symbol: &str,
+        document_id: i32,
+        relationship_type: Option<i32>,
+    ) -> Result<Vec<CollectionRelatedDocument>, Box<dyn std::error::Error>> {
+        let (language_index, related_documents) = {
+            let publication = self.get_publication(filename_symbol)?;
+            let language_index = publication
+                .get_document_by_id(document_id)?
+                .map(|document| document.meps_language_id);
+
+            (
+                language_index,
+                publication.get_related_documents(document_id, relationship_type)?,
+            )
+        };
+
+        let locations = self.locate_meps_documents(
+            &related_documents
+                .iter()
+                .map(|related_document| (related_document.meps_document_id, language_index))
+                .collect::<Vec<_>>(),
+        )?;
+
+        let mut collection_related_documents = Vec::new();
+        for related_document in related_documents {
+            let location = locations
+                .get(&(related_document.meps_document_id, language_index))
+                .and_then(|locations| locations.first().cloned());
+
+            collection_related_documents.push(CollectionRelatedDocument {
+                related_document,
+                location,
+            });
+        }
+
+        Ok(collection_related_documents)
+    }
+
+    /// Returns the daily text of `class` for `date` from the installed
+    /// "Examining the Scriptures Daily" edition in `language_index` that
+    /// covers it, if any.
+    pub fn dated_text_for(
+        &self,
+        date: NaiveDate,
+        class: i32,
+        language_index: i32,
+    ) -> Result<Option<CollectionDatedText>, Box<dyn std::error::Error>> {
+        let date_offset = dates::offset_from_date(date);
+
+        let filename_symbols = {
+            let db = self.readers.get()?;
+            let mut stmt = db.prepare(
+                "SELECT
+                    Publication.JwPub
+                FROM DatedText
+                INNER JOIN Publication ON Publication.PublicationId = DatedText.PublicationId
+                WHERE DatedText.Start <= ?1 AND
+                    DatedText.End >= ?1 AND
+                    DatedText.Class = ?2 AND
+                    Publication.KeySymbol = ?3 AND
+                    Publication.LanguageIndex = ?4
+                ORDER BY Publication.Timestamp DESC",
+            )?;
+            let mut rows = stmt.query(params![
+                date_offset,
+                class,
+                DAILY_TEXT_KEY_SYMBOL,
+                language_index
+            ])?;
+            let mut filename_symbols = Vec::new();
+            while let Some(row) = rows.next()? {
+                filename_symbols.push(row.get::<_, String>(0)?.replace(".jwpub", ""));
+            }
+            filename_symbols
+        };
+
+        if filename_symbols.is_empty() {
+            info!(
+                target: TARGET,
+                "No daily text edition installed for {} (Class {}, Language {}).",
+                date_offset.to_string().bright_blue(),
+                class.to_string().yellow(),
+                language_index.to_string().yellow()
+            );
+            return Ok(None);
+        }
+
+        for filename_symbol in filename_symbols {
+            let publication = self.get_publication(&filename_symbol)?;
+            let dated_texts = publication.get_dated_texts()?;
+
+            for dated_text in dated_texts.into_iter().filter(|dated_text| {
+                dated_text.first_date_offset <= date_offset
+                    && dated_text.last_date_offset >= date_offset
+            }) {
+                let document = publication
+                    .get_document_by_id(dated_text.document_id)?
+                    .unwrap_or_default();
+                if document.class != class.to_string() {
+                    continue;
+                }
+
+                debug!(
+                    target: TARGET,
+                    "Daily text for {} found on \"{}\" (DatedTextId: {}).",
+                    date_offset.to_string().bright_blue(),
+                    filename_symbol.green(),
+                    dated_text.id.to_string().yellow()
+                );
+
+                let content = publication
+                    .get_content_text_from(ContentTables::DatedText, dated_text.id)?
+                    .unwrap_or_default();
+
+                return Ok(Some(CollectionDatedText {
+                    publication_id: publication.catalog_id,
+                    publication_symbol: filename_symbol,
+                    document_id: dated_text.document_id,
+                    first_date_offset: dated_text.first_date_offset,
+                    last_date_offset: dated_text.last_date_offset,
+                    first_date: dated_text.first_date,
+                    last_date: dated_text.last_date,
+                    caption: dated_text.caption,
+                    caption_rich: dated_text.caption_rich,
+                    content,
+                }));
+            }
+        }
+
+        info!(
+            target: TARGET,
+            "Indexed daily text for {} wasn't found on installed editions.",
+            date_offset.to_string().bright_blue()
+        );
+        Ok(None)
+    }
+
+    /// Resolves the meeting workbook week and the Watchtower study article
+    /// scheduled for the week of `date` on installed issues in
+    /// `language_index`.
+    pub fn meeting_week_for(
+        &self,
+        date: NaiveDate,
+        language_index: i32,
+    ) -> Result<CollectionMeetingWeek, Box<dyn std::error::Error>> {
+        let date_offset = dates::offset_from_date(date);
+
+        Ok(CollectionMeetingWeek {
+            date,
+            date_offset,
+            meeting_workbook: self.scheduled_document_for(
+                date_offset,
+                MEETING_WORKBOOK_KEY_SYMBOL,
+                language_index,
+            )?,
+            watchtower_study: self.scheduled_document_for(
+                date_offset,
+                WATCHTOWER_KEY_SYMBOL,
+                language_index,
+            )?,
+        })
+    }
+
+    fn scheduled_document_for(
+        &self,
+        date_offset: i32,
+        key_symbol: &str,
+        language_index: i32,
+    ) -> Result<Option<CollectionScheduledDocument>, Box<dyn std::error::Error>> {
+        let candidates = {
+            let db = self.readers.get()?;
+            let mut stmt = db.prepare(
+                "SELECT DISTINCT
+                    Publication.JwPub,
+                    Publication.IssueTagNumber,
+                    Publication.Timestamp
+                FROM Publication
+                INNER JOIN DatedText ON DatedText.PublicationId = Publication.PublicationId
+                WHERE Publication.KeySymbol = ?2 AND
+                    Publication.LanguageIndex = ?3 AND
+                    Publication.FirstDatedTextDateOffset <= ?1 AND
+                    Publication.LastDatedTextDateOffset >= ?1 AND
+                    DatedText.Start <= ?1 AND
+                    DatedText.End >= ?1
+                ORDER BY Publication.Timestamp DESC",
             )?;
-            debug!(target: TARGET, "Caching connection...");
-            self.publication_cache.put(filename_symbol, publication);
-            return Ok(());
+            let mut rows = stmt.query(params![date_offset, key_symbol, language_index])?;
+            let mut candidates = Vec::new();
+            while let Some(row) = rows.next()? {
+                candidates.push((
+                    row.get::<_, String>(0)?.replace(".jwpub", ""),
+                    row.get::<_, Option<i32>>(1)?,
+                ));
+            }
+            candidates
+        };
+
+        for (filename_symbol, issue_tag_number) in candidates {
+            let publication = self.get_publication(&filename_symbol)?;
+            let dated_text = publication
+                .get_dated_texts()?
+                .into_iter()
+                .find(|dated_text| {
+                    dated_text.first_date_offset <= date_offset
+                        && dated_text.last_date_offset >= date_offset
+                });
+
+            if let Some(dated_text) = dated_text {
+                let document = publication
+                    .get_document_by_id(dated_text.document_id)?
+                    .unwrap_or_default();
+
+                debug!(
+                    target: TARGET,
+                    "Scheduled document for {} found on \"{}\" (MepsDocumentId: {}).",
+                    date_offset.to_string().bright_blue(),
+                    filename_symbol.green(),
+                    document.meps_document_id.to_string().yellow()
+                );
+
+                return Ok(Some(CollectionScheduledDocument {
+                    publication_id: publication.catalog_id,
+                    publication_symbol: filename_symbol,
+                    issue_tag_number,
+                    document_id: dated_text.document_id,
+                    meps_document_id: document.meps_document_id,
+                    title: document.title,
+                    caption: dated_text.caption,
+                    first_date_offset: dated_text.first_date_offset,
+                    last_date_offset: dated_text.last_date_offset,
+                    first_date: dated_text.first_date,
+                    last_date: dated_text.last_date,
+                }));
+            }
+        }
+
+        info!(
+            target: TARGET,
+            "No \"{}\" issue installed for the week of {}.",
+            key_symbol.magenta(),
+            date_offset.to_string().bright_blue()
+        );
+        Ok(None)
+    }
+
+    /// Gets a publication connection from cache, opening it if needed,
+    /// without changing the current open publication.
+    pub fn get_publication(
+        &self,
+        filename_symbol: &str,
+    ) -> Result<Arc<Publication>, Box<dyn std::error::Error>> {
+        if let Some(publication) = self
+            .publication_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(filename_symbol)
+        {
+            return Ok(publication.clone());
         }
 
-        error!(target: TARGET, "Publication requested doesn't exist.");
-        Err("Publication not found in catalog".into())
+        let publication_metadata = self
+            .get_publication_collection_meta(filename_symbol)?
+            .ok_or("Publication not found in catalog")?;
+
+        debug!(target: TARGET, "Opening connection with \"{}\"...", filename_symbol);
+        let publication = Arc::new(Publication::from_database(
+            PathBuf::from(publication_metadata.database_path),
+            publication_metadata.id as i64,
+        )?);
+        self.publication_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(filename_symbol.to_owned(), publication.clone());
+
+        Ok(publication)
+    }
+
+    /// Resolves `filename` inside the directory of an installed publication
+    /// without opening it. Paths escaping that directory aren't resolved.
+    pub fn get_publication_media_path(
+        &self,
+        filename_symbol: &str,
+        filename: &str,
+    ) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+        let filename = Path::new(filename);
+        if !filename
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Ok(None);
+        }
+
+        Ok(self
+            .get_publication_collection_meta(filename_symbol)?
+            .and_then(|publication_metadata| {
+                PathBuf::from(publication_metadata.database_path)
+                    .parent()
+                    .map(|location| location.join(filename))
+            }))
+    }
+
+    pub fn get_current_publication(&self) -> Option<Arc<Publication>> {
+        let current_open = self
+            .current_open
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        match current_open {
+            Some(filename_symbol) => self.get_publication(&filename_symbol).ok(),
+            None => None,
+        }
+    }
+
+    pub fn open_publication_connection(
+        &self,
+        filename_symbol: String,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Err(err) = self.get_publication(&filename_symbol) {
+            error!(target: TARGET, "Publication requested doesn't exist.");
+            return Err(err);
+        }
+
+        debug!(target: TARGET, "Setting \"{}\" as current open publication...", filename_symbol);
+        *self
+            .current_open
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(filename_symbol);
+        Ok(())
     }
 }
 
@@ -0,0 +1,152 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+// Placeholder written to `FirstDatedTextDateOffset` and `LastDatedTextDateOffset`
+// when a publication doesn't have any dated text.
+pub const NO_DATE_OFFSET: i32 = 19691231;
+
+/// Converts a `YYYYMMDD` date offset (e.g. `20241118`) into a date.
+pub fn date_from_offset(offset: i32) -> Option<NaiveDate> {
+    if offset <= 0 || offset == NO_DATE_OFFSET {
+        return None;
+    }
+
+    NaiveDate::from_ymd_opt(
+        offset / 10000,
+        (offset / 100 % 100) as u32,
+        (offset % 100) as u32,
+    )
+}
+
+/// Converts a date into its `YYYYMMDD` date offset.
+pub fn offset_from_date(date: NaiveDate) -> i32 {
+    date.year() * 10000 + date.month() as i32 * 100 + date.day() as i32
+}
+
+/// Period covered by a periodical issue, decoded from its `IssueTagNumber`.
+///
+/// Issue tags are encoded as `YYYYMMDD`, where the day is `00` for
+/// monthly issues (e.g. `20241100`) and the issue day for semimonthly
+/// ones (e.g. `20150115`).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IssuePeriod {
+    pub year: i32,
+    pub month: u32,
+    pub day: Option<u32>,
+}
+
+impl IssuePeriod {
+    pub fn from_issue_tag(issue_tag_number: i32) -> Option<Self> {
+        if issue_tag_number <= 0 {
+            return None;
+        }
+
+        let period = Self {
+            year: issue_tag_number / 10000,
+            month: (issue_tag_number / 100 % 100) as u32,
+            day: match (issue_tag_number % 100) as u32 {
+                0 => None,
+                day => Some(day),
+            },
+        };
+
+        period.start_date().map(|_| period)
+    }
+
+    pub fn issue_tag_number(&self) -> i32 {
+        self.year * 10000 + self.month as i32 * 100 + self.day.unwrap_or(0) as i32
+    }
+
+    /// First day covered by the issue.
+    pub fn start_date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year, self.month, self.day.unwrap_or(1))
+    }
+}
+
+impl From<NaiveDate> for IssuePeriod {
+    // Assumes a monthly issue, the format used by every current periodical.
+    fn from(date: NaiveDate) -> Self {
+        Self {
+            year: date.year(),
+            month: date.month(),
+            day: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn offsets_round_trip() {
+        assert_eq!(date_from_offset(20241118), Some(date(2024, 11, 18)));
+        assert_eq!(offset_from_date(date(2024, 11, 18)), 20241118);
+
+        for day in [date(2000, 1, 1), date(2024, 2, 29), date(2025, 12, 31)] {
+            assert_eq!(date_from_offset(offset_from_date(day)), Some(day));
+        }
+    }
+
+    #[test]
+    fn missing_offsets_have_no_date() {
+        assert_eq!(date_from_offset(NO_DATE_OFFSET), None);
+        assert_eq!(date_from_offset(0), None);
+        assert_eq!(date_from_offset(-20241118), None);
+    }
+
+    #[test]
+    fn invalid_offsets_have_no_date() {
+        for offset in [20240230, 20231301, 20241100, 20241132, 1, i32::MAX] {
+            assert_eq!(date_from_offset(offset), None, "{}", offset);
+        }
+    }
+
+    #[test]
+    fn monthly_issue_tag() {
+        let period = IssuePeriod::from_issue_tag(20241100).unwrap();
+        assert_eq!(
+            period,
+            IssuePeriod {
+                year: 2024,
+                month: 11,
+                day: None
+            }
+        );
+        assert_eq!(period.start_date(), Some(date(2024, 11, 1)));
+        assert_eq!(period.issue_tag_number(), 20241100);
+        assert_eq!(IssuePeriod::from(date(2024, 11, 18)), period);
+    }
+
+    #[test]
+    fn semimonthly_issue_tag() {
+        let period = IssuePeriod::from_issue_tag(20150115).unwrap();
+        assert_eq!(
+            period,
+            IssuePeriod {
+                year: 2015,
+                month: 1,
+                day: Some(15)
+            }
+        );
+        assert_eq!(period.start_date(), Some(date(2015, 1, 15)));
+        assert_eq!(period.issue_tag_number(), 20150115);
+        assert!(period < IssuePeriod::from_issue_tag(20150200).unwrap());
+    }
+
+    #[test]
+    fn invalid_issue_tags() {
+        for issue_tag_number in [0, -20241100, 20241300, 20240230, 2024, i32::MAX] {
+            assert_eq!(
+                IssuePeriod::from_issue_tag(issue_tag_number),
+                None,
+                "{}",
+                issue_tag_number
+            );
+        }
+    }
+}
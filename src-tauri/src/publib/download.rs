@@ -0,0 +1,530 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+use md5::{Digest, Md5};
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use super::remote::http_client;
+
+const TARGET: &'static str = "catalog::download";
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub const CANCELLED_ERROR: &'static str = "Download cancelled";
+
+// Attempts made for each job before failing it, waiting twice as long
+// after each failure
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+pub type JobId = u64;
+
+/// What a downloaded file must match before it's used.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ExpectedFile {
+    pub size: Option<u64>,
+    // Hex encoded MD5, as published by the pub-media API
+    pub md5: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    pub total: u64,
+}
+
+/// File a download of `destination` is written to until verified.
+pub fn partial_path_of(destination: &Path) -> PathBuf {
+    let mut partial_path = destination.as_os_str().to_owned();
+    partial_path.push(".part");
+    PathBuf::from(partial_path)
+}
+
+/// Downloads `url` to `destination`, going through a `.part` file that is
+/// resumed with a `Range` request when a previous attempt left one.
+///
+/// Checks `cancel` between chunks, leaving the partial file in place so a
+/// later call can resume it. The file is only moved to `destination` once
+/// it matches `expected`.
+pub fn download_file(
+    url: &str,
+    destination: &Path,
+    expected: &ExpectedFile,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(DownloadProgress),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let partial_path = partial_path_of(destination);
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut resume_from = fs::metadata(&partial_path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    // A partial file as big as the expected one only needs verifying
+    let already_complete = matches!(expected.size, Some(size) if size > 0 && resume_from >= size);
+    let mut expected = expected.clone();
+
+    if !already_complete {
+        let mut request = http_client()?.get(url);
+        if resume_from > 0 {
+            debug!(target: TARGET, "Resuming {} from byte {}...", url.green(), resume_from.to_string().yellow());
+            request = request.header(header::RANGE, format!("bytes={}-", resume_from));
+        } else {
+            debug!(target: TARGET, "Downloading {}...", url.green());
+        }
+
+        let mut response = request.send()?.error_for_status()?;
+
+        // Servers ignoring the range answer with the whole file
+        if resume_from > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+            warn!(target: TARGET, "Server didn't resume {}, starting over.", url);
+            resume_from = 0;
+        }
+
+        let total = match response.content_length() {
+            Some(length) => resume_from + length,
+            None => expected.size.unwrap_or(0),
+        };
+        // Without an expected size, the one announced by the server still
+        // catches truncated transfers
+        if expected.size.is_none() && total > 0 {
+            expected.size = Some(total);
+        }
+
+        let mut partial_file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume_from > 0)
+            .truncate(resume_from == 0)
+            .open(&partial_path)?;
+
+        let mut downloaded = resume_from;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        on_progress(DownloadProgress { downloaded, total });
+
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                info!(target: TARGET, "Download of {} cancelled at byte {}.", url, downloaded);
+                return Err(CANCELLED_ERROR.into());
+            }
+
+            let read = match response.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            partial_file.write_all(&buffer[..read])?;
+            downloaded += read as u64;
+            on_progress(DownloadProgress { downloaded, total });
+        }
+
+        partial_file.flush()?;
+    }
+
+    if let Err(err) = verify_file(&partial_path, &expected) {
+        // A corrupt partial file can't be resumed, start over next time
+        let _ = fs::remove_file(&partial_path);
+        return Err(err);
+    }
+
+    fs::rename(&partial_path, destination)?;
+    debug!(target: TARGET, "Downloaded {} to {}.", url.green(), destination.display().to_string().green());
+
+    Ok(())
+}
+
+/// Checks the size and MD5 hash of `path` against `expected`.
+pub fn verify_file(path: &Path, expected: &ExpectedFile) -> Result<(), Box<dyn std::error::Error>> {
+    let size = fs::metadata(path)?.len();
+    if let Some(expected_size) = expected.size {
+        if expected_size > 0 && size != expected_size {
+            return Err(format!(
+                "Downloaded file has {} bytes, expected {}",
+                size, expected_size
+            )
+            .into());
+        }
+    }
+
+    if let Some(expected_md5) = &expected.md5 {
+        let md5 = file_md5(path)?;
+        if !md5.eq_ignore_ascii_case(expected_md5) {
+            return Err(format!(
+                "Downloaded file checksum {} doesn't match {}",
+                md5, expected_md5
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Hex encoded MD5 hash of the file at `path`.
+pub fn file_md5(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Md5::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadKind {
+    Asset,
+    Catalog,
+    Publication,
+    Media,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DownloadRequest {
+    pub kind: DownloadKind,
+    // Shown to the user, e.g. the publication symbol or the file name
+    pub label: String,
+    pub url: String,
+    pub destination: PathBuf,
+    pub expected: ExpectedFile,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadState {
+    Queued,
+    Downloading,
+    Retrying,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl DownloadState {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            DownloadState::Completed | DownloadState::Failed | DownloadState::Cancelled
+        )
+    }
+}
+
+/// Progress of a download job, sent on every state change and whole
+/// percent downloaded.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DownloadEvent {
+    pub job_id: JobId,
+    pub kind: DownloadKind,
+    pub label: String,
+    pub state: DownloadState,
+    pub downloaded: u64,
+    pub total: u64,
+    pub attempt: u32,
+    pub error: Option<String>,
+}
+
+struct Job {
+    id: JobId,
+    request: DownloadRequest,
+    cancel: Arc<AtomicBool>,
+    events: Sender<DownloadEvent>,
+}
+
+struct Shared {
+    queue: Mutex<VecDeque<Job>>,
+    available: Condvar,
+    // Cancellation flag and last event of unfinished jobs
+    jobs: Mutex<HashMap<JobId, (Arc<AtomicBool>, DownloadEvent)>>,
+    next_id: AtomicU64,
+    listener: Box<dyn Fn(&DownloadEvent) + Send + Sync>,
+}
+
+/// Queue of downloads served by a fixed set of worker threads.
+///
+/// Every job is retried with backoff, resumed from its partial file and
+/// verified before completing. Events of each job are sent both to the
+/// manager listener and to the ticket returned when queueing it.
+pub struct DownloadManager {
+    shared: Arc<Shared>,
+}
+
+/// Handle on jobs queued together.
+pub struct DownloadTicket {
+    pub job_ids: Vec<JobId>,
+    events: Receiver<DownloadEvent>,
+}
+
+impl DownloadTicket {
+    /// Blocks until every job of the ticket finished, calling `on_event`
+    /// for each of their events. Fails with the first job error.
+    pub fn wait(
+        self,
+        mut on_event: impl FnMut(&DownloadEvent),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut pending = self.job_ids.len();
+        let mut first_error = None;
+
+        while pending > 0 {
+            let event = self
+                .events
+                .recv()
+                .map_err(|_| "Download worker stopped unexpectedly")?;
+            on_event(&event);
+
+            if event.state.is_finished() {
+                pending -= 1;
+                if first_error.is_none() {
+                    first_error = match event.state {
+                        DownloadState::Cancelled => Some(CANCELLED_ERROR.to_owned()),
+                        _ => event.error,
+                    };
+                }
+            }
+        }
+
+        match first_error {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl DownloadManager {
+    pub fn new(
+        concurrency: usize,
+        listener: impl Fn(&DownloadEvent) + Send + Sync + 'static,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::new()),
+            available: Condvar::new(),
+            jobs: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            listener: Box::new(listener),
+        });
+
+        for index in 0..concurrency.max(1) {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("download-worker-{}", index))
+                .spawn(move || shared.work())
+                .expect("Couldn't spawn download worker");
+        }
+
+        Self { shared }
+    }
+
+    pub fn enqueue(&self, requests: Vec<DownloadRequest>) -> DownloadTicket {
+        let (sender, receiver) = mpsc::channel();
+        let mut job_ids = Vec::with_capacity(requests.len());
+
+        for request in requests {
+            let job = Job {
+                id: self.shared.next_id.fetch_add(1, Ordering::Relaxed),
+                cancel: Arc::new(AtomicBool::new(false)),
+                events: sender.clone(),
+                request,
+            };
+            job_ids.push(job.id);
+
+            debug!(target: TARGET, "Queueing download {} ({})...", job.request.label.bright_magenta(), job.id.to_string().yellow());
+            self.shared.publish(
+                &job,
+                DownloadEvent {
+                    job_id: job.id,
+                    kind: job.request.kind,
+                    label: job.request.label.clone(),
+                    state: DownloadState::Queued,
+                    downloaded: 0,
+                    total: job.request.expected.size.unwrap_or(0),
+                    attempt: 0,
+                    error: None,
+                },
+            );
+
+            self.shared
+                .queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push_back(job);
+            self.shared.available.notify_one();
+        }
+
+        DownloadTicket {
+            job_ids,
+            events: receiver,
+        }
+    }
+
+    /// Queues a single download and blocks until it finishes.
+    pub fn download(
+        &self,
+        request: DownloadRequest,
+        on_event: impl FnMut(&DownloadEvent),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.enqueue(vec![request]).wait(on_event)
+    }
+
+    /// Cancels a queued or running job. Returns `false` when it already
+    /// finished or doesn't exist.
+    pub fn cancel(&self, job_id: JobId) -> bool {
+        match self
+            .shared
+            .jobs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&job_id)
+        {
+            Some((cancel, _)) => {
+                info!(target: TARGET, "Cancelling download job {}...", job_id.to_string().yellow());
+                cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Last event of every unfinished job.
+    pub fn jobs(&self) -> Vec<DownloadEvent> {
+        let mut jobs: Vec<DownloadEvent> = self
+            .shared
+            .jobs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .map(|(_, event)| event.clone())
+            .collect();
+        jobs.sort_by_key(|event| event.job_id);
+        jobs
+    }
+}
+
+impl Shared {
+    fn work(&self) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+                loop {
+                    match queue.pop_front() {
+                        Some(job) => break job,
+                        None => {
+                            queue = self
+                                .available
+                                .wait(queue)
+                                .unwrap_or_else(PoisonError::into_inner)
+                        }
+                    }
+                }
+            };
+
+            self.run(job);
+        }
+    }
+
+    fn publish(&self, job: &Job, event: DownloadEvent) {
+        {
+            let mut jobs = self.jobs.lock().unwrap_or_else(PoisonError::into_inner);
+            if event.state.is_finished() {
+                jobs.remove(&job.id);
+            } else {
+                jobs.insert(job.id, (job.cancel.clone(), event.clone()));
+            }
+        }
+
+        (self.listener)(&event);
+        // The ticket may have been dropped by a caller not waiting on it
+        let _ = job.events.send(event);
+    }
+
+    fn run(&self, job: Job) {
+        let mut event = DownloadEvent {
+            job_id: job.id,
+            kind: job.request.kind,
+            label: job.request.label.clone(),
+            state: DownloadState::Downloading,
+            downloaded: 0,
+            total: job.request.expected.size.unwrap_or(0),
+            attempt: 0,
+            error: None,
+        };
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            if job.cancel.load(Ordering::Relaxed) {
+                event.state = DownloadState::Cancelled;
+                self.publish(&job, event);
+                return;
+            }
+
+            event.attempt = attempt;
+            event.state = DownloadState::Downloading;
+            event.error = None;
+            self.publish(&job, event.clone());
+
+            let mut last_percent = None;
+            let result = download_file(
+                &job.request.url,
+                &job.request.destination,
+                &job.request.expected,
+                &job.cancel,
+                |progress| {
+                    let percent = match progress.total {
+                        0 => None,
+                        total => Some(progress.downloaded * 100 / total),
+                    };
+                    if percent.is_none() || percent != last_percent {
+                        last_percent = percent;
+                        event.downloaded = progress.downloaded;
+                        event.total = progress.total;
+                        self.publish(&job, event.clone());
+                    }
+                },
+            );
+
+            match result {
+                Ok(()) => {
+                    event.state = DownloadState::Completed;
+                    self.publish(&job, event);
+                    return;
+                }
+                Err(_) if job.cancel.load(Ordering::Relaxed) => {
+                    event.state = DownloadState::Cancelled;
+                    self.publish(&job, event);
+                    return;
+                }
+                Err(err) if attempt == MAX_ATTEMPTS => {
+                    error!(target: TARGET, "Download {} failed: {}", job.request.label, err);
+                    event.state = DownloadState::Failed;
+                    event.error = Some(err.to_string());
+                    self.publish(&job, event);
+                    return;
+                }
+                Err(err) => {
+                    let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+                    warn!(target: TARGET, "Download {} failed (attempt {}), retrying in {:?}: {}", job.request.label, attempt, delay, err);
+                    event.state = DownloadState::Retrying;
+                    event.error = Some(err.to_string());
+                    self.publish(&job, event.clone());
+
+                    let retry_at = Instant::now() + delay;
+                    while Instant::now() < retry_at && !job.cancel.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                }
+            }
+        }
+    }
+}
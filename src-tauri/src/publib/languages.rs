@@ -0,0 +1,322 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::{PoisonError, RwLock},
+};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use super::{media::MediaLanguage, Catalog};
+
+const TARGET: &'static str = "catalog::languages";
+
+// Languages known ahead of any installed publication, a new entry ships
+// with the app
+const BUNDLED_LANGUAGES: &'static str = include_str!("../../assets/languages.json");
+
+/// Whether `language_code` looks like a jw.org language symbol, e.g. `E`
+/// or `pt-PT`. Codes end up in cache file names, so nothing else is
+/// accepted.
+pub fn is_valid_language_code(language_code: &str) -> bool {
+    !language_code.is_empty()
+        && language_code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Fails on language codes rejected by `is_valid_language_code`.
+pub fn check_language_code(language_code: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !is_valid_language_code(language_code) {
+        return Err(format!("Invalid language code \"{}\"", language_code).into());
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Language {
+    /// MEPS language index, as stored by publications and the catalog.
+    pub meps_index: i32,
+    /// jw.org language symbol, e.g. `T`.
+    pub symbol: String,
+    /// ISO code, e.g. `pt`.
+    pub iso_code: Option<String>,
+    /// English name.
+    pub name: Option<String>,
+    /// Name in the language itself.
+    pub vernacular: Option<String>,
+    pub script: Option<String>,
+    /// Unknown for languages missing from the bundled table until named
+    /// with the media catalog, text direction is then told by the content
+    /// (`dir="auto"`).
+    pub direction: Option<TextDirection>,
+    #[serde(default)]
+    pub is_sign_language: bool,
+}
+
+impl Language {
+    fn learned(meps_index: i32, symbol: &str) -> Self {
+        Self {
+            meps_index,
+            symbol: symbol.to_owned(),
+            iso_code: None,
+            name: None,
+            vernacular: None,
+            script: None,
+            direction: None,
+            is_sign_language: false,
+        }
+    }
+
+    fn named(&self, media_language: &MediaLanguage) -> Self {
+        Self {
+            meps_index: self.meps_index,
+            symbol: self.symbol.clone(),
+            iso_code: media_language.locale.clone(),
+            name: media_language.name.clone(),
+            vernacular: media_language.vernacular.clone(),
+            script: media_language.script.clone(),
+            direction: Some(match media_language.is_rtl {
+                true => TextDirection::Rtl,
+                false => TextDirection::Ltr,
+            }),
+            is_sign_language: media_language.is_sign_language,
+        }
+    }
+
+    /// Fills the names, codes and direction missing from `self` with the
+    /// ones of `other`. Returns `true` when anything was filled.
+    fn enrich(&mut self, other: &Language) -> bool {
+        let mut enriched = false;
+        if self.direction.is_none() && other.direction.is_some() {
+            self.direction = other.direction;
+            enriched = true;
+        }
+        for (field, value) in [
+            (&mut self.iso_code, &other.iso_code),
+            (&mut self.name, &other.name),
+            (&mut self.vernacular, &other.vernacular),
+            (&mut self.script, &other.script),
+        ] {
+            if field.is_none() && value.is_some() {
+                *field = value.clone();
+                enriched = true;
+            }
+        }
+        enriched
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct InstalledLanguage {
+    /// Known only once the MEPS index was mapped to a symbol.
+    pub language: Option<Language>,
+    pub meps_index: i32,
+    pub publication_count: usize,
+}
+
+/// Maps MEPS language indexes to their codes, names and direction.
+///
+/// Seeded from the bundled table, which other sources never override.
+/// Indexes missing from it are learned from the file names of installed
+/// publications (`{symbol}_{language}.jwpub`), then named with the media
+/// catalog languages. The registry is kept in `path` so it's available
+/// offline.
+pub struct LanguageRegistry {
+    path: PathBuf,
+    bundled: BTreeMap<i32, Language>,
+    languages: RwLock<BTreeMap<i32, Language>>,
+}
+
+impl LanguageRegistry {
+    pub fn init<T: Into<PathBuf>>(path: T) -> Result<Self, Box<dyn std::error::Error>> {
+        let path: PathBuf = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let bundled: BTreeMap<i32, Language> =
+            serde_json::from_str::<Vec<Language>>(BUNDLED_LANGUAGES)
+                .expect("Invalid bundled language table")
+                .into_iter()
+                .map(|language| (language.meps_index, language))
+                .collect();
+
+        let saved: Vec<Language> = match fs::File::open(&path) {
+            Ok(file) => serde_json::from_reader(file).unwrap_or_else(|err| {
+                warn!(target: TARGET, "Ignoring invalid language registry: {}", err);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        let mut languages = bundled.clone();
+        for language in saved {
+            match languages.get_mut(&language.meps_index) {
+                Some(bundled_language) => {
+                    bundled_language.enrich(&language);
+                }
+                None => {
+                    languages.insert(language.meps_index, language);
+                }
+            }
+        }
+
+        Ok(Self {
+            path,
+            bundled,
+            languages: RwLock::new(languages),
+        })
+    }
+
+    pub fn get(&self, meps_index: i32) -> Option<Language> {
+        self.languages
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&meps_index)
+            .cloned()
+    }
+
+    pub fn get_by_symbol(&self, symbol: &str) -> Option<Language> {
+        self.languages
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .find(|language| language.symbol == symbol)
+            .cloned()
+    }
+
+    /// Every known language, by MEPS index.
+    pub fn languages(&self) -> Vec<Language> {
+        self.languages
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Records that `meps_index` is the language `symbol`. Returns `true`
+    /// when the registry changed.
+    pub fn learn(&self, meps_index: i32, symbol: &str) -> bool {
+        if let Some(language) = self.bundled.get(&meps_index) {
+            if language.symbol != symbol {
+                warn!(target: TARGET, "MEPS language {} is {}, ignoring {}.", meps_index, language.symbol, symbol);
+            }
+            return false;
+        }
+
+        let mut languages = self
+            .languages
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        match languages.get_mut(&meps_index) {
+            Some(language) if language.symbol == symbol => false,
+            Some(language) => {
+                warn!(target: TARGET, "MEPS language {} changed from {} to {}.", meps_index, language.symbol, symbol);
+                language.symbol = symbol.to_owned();
+                true
+            }
+            None => {
+                debug!(target: TARGET, "Learned MEPS language {} is {}.", meps_index.to_string().yellow(), symbol.bright_magenta());
+                languages.insert(meps_index, Language::learned(meps_index, symbol));
+                true
+            }
+        }
+    }
+
+    /// Learns the language of every installed publication, saving the
+    /// registry when it changed. Returns how many languages were learned.
+    pub fn learn_from_catalog(
+        &self,
+        catalog: &Catalog,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut learned = 0;
+        for publication in catalog.get_publications()? {
+            if let Some(symbol) = publication.language_code() {
+                if self.learn(publication.language_index, symbol) {
+                    learned += 1;
+                }
+            }
+        }
+
+        if learned > 0 {
+            self.save()?;
+        }
+        Ok(learned)
+    }
+
+    /// Names the learned languages with the media catalog languages, and
+    /// fills what the bundled table lacks. Returns how many languages were
+    /// updated.
+    pub fn merge_media_languages(
+        &self,
+        media_languages: &[MediaLanguage],
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut updated = 0;
+        {
+            let mut languages = self
+                .languages
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            for language in languages.values_mut() {
+                let Some(media_language) = media_languages
+                    .iter()
+                    .find(|media_language| media_language.code == language.symbol)
+                else {
+                    continue;
+                };
+
+                let named = language.named(media_language);
+                if self.bundled.contains_key(&language.meps_index) {
+                    if language.enrich(&named) {
+                        updated += 1;
+                    }
+                } else {
+                    *language = named;
+                    updated += 1;
+                }
+            }
+        }
+
+        self.save()?;
+        Ok(updated)
+    }
+
+    /// Languages of the installed publications, with how many are
+    /// installed in each.
+    pub fn installed_languages(
+        &self,
+        catalog: &Catalog,
+    ) -> Result<Vec<InstalledLanguage>, Box<dyn std::error::Error>> {
+        let mut counts: BTreeMap<i32, usize> = BTreeMap::new();
+        for publication in catalog.get_publications()? {
+            *counts.entry(publication.language_index).or_default() += 1;
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|(meps_index, publication_count)| InstalledLanguage {
+                language: self.get(meps_index),
+                meps_index,
+                publication_count,
+            })
+            .collect())
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let languages = self.languages();
+        let file = fs::File::create(&self.path)?;
+        serde_json::to_writer_pretty(file, &languages)?;
+        Ok(())
+    }
+}
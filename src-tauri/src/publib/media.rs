@@ -0,0 +1,485 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+use colored::Colorize;
+use flate2::read::GzDecoder;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    download::{partial_path_of, DownloadKind, DownloadManager, DownloadRequest, ExpectedFile},
+    languages::check_language_code,
+    remote::{pub_media_query, PubMediaFile, PubMediaQuery},
+    Catalog, RemoteCatalog,
+};
+
+const TARGET: &'static str = "catalog::media";
+
+const MEDIA_CATALOG_PATH: &'static str = "catalogs/media";
+// Downloaded catalogs are used as is for a day, or longer when offline
+const CATALOG_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+// Resolved media links, cached in memory only since URLs may change
+const LINKS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+const MAX_CACHED_CATALOGS: usize = 4;
+const MAX_CACHED_LINKS: usize = 64;
+
+const AUDIO_FORMAT: &'static str = "MP3";
+const VIDEO_FORMAT: &'static str = "MP4";
+
+/// Language of the media catalog (`languages.json.gz`).
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MediaLanguage {
+    pub code: String,
+    pub locale: Option<String>,
+    pub name: Option<String>,
+    pub vernacular: Option<String>,
+    pub script: Option<String>,
+    #[serde(default, rename(deserialize = "isRTL"))]
+    pub is_rtl: bool,
+    #[serde(default, rename(deserialize = "isSignLanguage"))]
+    pub is_sign_language: bool,
+}
+
+// `languages.json.gz` has been served both as a bare list and wrapped
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MediaLanguages {
+    Wrapped { languages: Vec<MediaLanguage> },
+    List(Vec<MediaLanguage>),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct MediaCategory {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub category_type: Option<String>,
+    pub name: Option<String>,
+    pub parent_category: Option<String>,
+}
+
+/// Parts of a media item natural key, linking it to a publication.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct MediaKeyParts {
+    pub pub_symbol: Option<String>,
+    pub issue_date: Option<i64>,
+    pub track: Option<i32>,
+    pub format_code: Option<String>,
+    pub language_code: Option<String>,
+    #[serde(rename(deserialize = "docID"))]
+    pub doc_id: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct MediaItem {
+    pub natural_key: String,
+    pub language_agnostic_natural_key: Option<String>,
+    #[serde(default)]
+    pub key_parts: MediaKeyParts,
+    pub primary_category: Option<String>,
+    pub title: Option<String>,
+    pub first_published: Option<String>,
+    /// Length in seconds.
+    pub duration: Option<f64>,
+}
+
+/// Media catalog of a single language (`{language_code}.json.gz`).
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct MediaLanguageCatalog {
+    pub categories: Vec<MediaCategory>,
+    pub items: Vec<MediaItem>,
+}
+
+// Each line of a language catalog is one of these records
+#[derive(Deserialize)]
+struct MediaCatalogRecord {
+    #[serde(rename = "type")]
+    record_type: String,
+    o: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Audio,
+    Video,
+}
+
+/// A file of a recording, in one quality.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MediaVariant {
+    pub label: Option<String>,
+    pub url: String,
+    pub checksum: Option<String>,
+    pub filesize: u64,
+    pub mimetype: Option<String>,
+    pub bit_rate: Option<f64>,
+    pub frame_height: Option<i32>,
+}
+
+/// Audio recording or video of a publication track or document, with
+/// every quality it's available in.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MediaRecording {
+    pub kind: MediaKind,
+    pub title: Option<String>,
+    pub track: Option<i32>,
+    pub docid: Option<i32>,
+    pub booknum: Option<i32>,
+    pub duration: Option<f64>,
+    pub variants: Vec<MediaVariant>,
+}
+
+/// Media catalog and audio/video lookup over the jw-cdn servers set on
+/// the remote catalog.
+///
+/// Catalog files are kept compressed in `cache_path` and parsed on demand.
+pub struct MediaCatalog {
+    cache_path: PathBuf,
+    remote: Arc<RemoteCatalog>,
+    downloader: Arc<DownloadManager>,
+
+    languages: RwLock<Option<Arc<Vec<MediaLanguage>>>>,
+    catalogs: Mutex<LruCache<String, Arc<MediaLanguageCatalog>>>,
+    links: Mutex<LruCache<String, (Instant, Arc<Vec<MediaRecording>>)>>,
+}
+
+impl MediaCatalog {
+    pub fn init<T: Into<PathBuf>>(
+        location: T,
+        remote: Arc<RemoteCatalog>,
+        downloader: Arc<DownloadManager>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let cache_path: PathBuf = location.into();
+        if !cache_path.exists() {
+            fs::create_dir_all(&cache_path)?;
+        }
+
+        Ok(Self {
+            cache_path,
+            remote,
+            downloader,
+            languages: RwLock::new(None),
+            catalogs: Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_CACHED_CATALOGS).unwrap(),
+            )),
+            links: Mutex::new(LruCache::new(NonZeroUsize::new(MAX_CACHED_LINKS).unwrap())),
+        })
+    }
+
+    /// Languages media is published in. `refresh` downloads the list again
+    /// even when the cached one is recent.
+    pub fn get_languages(
+        &self,
+        refresh: bool,
+    ) -> Result<Arc<Vec<MediaLanguage>>, Box<dyn std::error::Error>> {
+        if !refresh {
+            if let Some(languages) = self
+                .languages
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+            {
+                return Ok(languages);
+            }
+        }
+
+        let path = self.cached_file("languages.json.gz", refresh)?;
+        let languages = match serde_json::from_reader(GzDecoder::new(fs::File::open(&path)?))? {
+            MediaLanguages::Wrapped { languages } => languages,
+            MediaLanguages::List(languages) => languages,
+        };
+        let languages = Arc::new(languages);
+        *self
+            .languages
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(languages.clone());
+
+        Ok(languages)
+    }
+
+    /// Media catalog of `language_code` (e.g. `T`).
+    pub fn get_language_catalog(
+        &self,
+        language_code: &str,
+        refresh: bool,
+    ) -> Result<Arc<MediaLanguageCatalog>, Box<dyn std::error::Error>> {
+        check_language_code(language_code)?;
+        if !refresh {
+            if let Some(catalog) = self
+                .catalogs
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(language_code)
+            {
+                return Ok(catalog.clone());
+            }
+        }
+
+        let path = self.cached_file(&format!("{}.json.gz", language_code), refresh)?;
+        let catalog = Arc::new(parse_language_catalog(&path)?);
+        debug!(target: TARGET, "Loaded {} media items for language {}.", catalog.items.len().to_string().yellow(), language_code.bright_magenta());
+        self.catalogs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(language_code.to_owned(), catalog.clone());
+
+        Ok(catalog)
+    }
+
+    /// Catalog items of `language_code`, only those of `pub_symbol` when
+    /// given.
+    pub fn get_media_items(
+        &self,
+        language_code: &str,
+        pub_symbol: Option<&str>,
+    ) -> Result<Vec<MediaItem>, Box<dyn std::error::Error>> {
+        let catalog = self.get_language_catalog(language_code, false)?;
+
+        Ok(catalog
+            .items
+            .iter()
+            .filter(|item| match pub_symbol {
+                Some(pub_symbol) => item.key_parts.pub_symbol.as_deref() == Some(pub_symbol),
+                None => true,
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Audio recordings and videos of a publication, one per track.
+    pub fn get_publication_media(
+        &self,
+        symbol: &str,
+        issue: Option<i32>,
+        language_code: &str,
+    ) -> Result<Arc<Vec<MediaRecording>>, Box<dyn std::error::Error>> {
+        check_language_code(language_code)?;
+        self.lookup(
+            format!("pub:{}:{}:{}", symbol, issue.unwrap_or(0), language_code),
+            &PubMediaQuery::Publication { symbol, issue },
+            language_code,
+        )
+    }
+
+    /// Audio recordings and videos of a single document.
+    pub fn get_document_media(
+        &self,
+        meps_document_id: i32,
+        language_code: &str,
+    ) -> Result<Arc<Vec<MediaRecording>>, Box<dyn std::error::Error>> {
+        check_language_code(language_code)?;
+        self.lookup(
+            format!("doc:{}:{}", meps_document_id, language_code),
+            &PubMediaQuery::Document(meps_document_id),
+            language_code,
+        )
+    }
+
+    /// Audio recordings and videos of an installed publication, in its
+    /// own language.
+    pub fn get_installed_publication_media(
+        &self,
+        catalog: &Catalog,
+        filename_symbol: &str,
+    ) -> Result<Arc<Vec<MediaRecording>>, Box<dyn std::error::Error>> {
+        let publication = catalog
+            .get_publication_collection_meta(filename_symbol)?
+            .ok_or("Publication not found in catalog")?;
+        let language_code = publication
+            .language_code()
+            .ok_or("Couldn't tell the language of the publication")?;
+        let (symbol, issue) = pub_media_query(
+            &publication.symbol,
+            Some(&publication.key_symbol),
+            publication.issue_tag_number.unwrap_or(0),
+        );
+
+        self.get_publication_media(symbol, issue, language_code)
+    }
+
+    /// Audio recordings and videos of a document of an installed
+    /// publication, in the publication language.
+    pub fn get_installed_document_media(
+        &self,
+        catalog: &Catalog,
+        filename_symbol: &str,
+        document_id: i32,
+    ) -> Result<Arc<Vec<MediaRecording>>, Box<dyn std::error::Error>> {
+        let publication = catalog
+            .get_publication_collection_meta(filename_symbol)?
+            .ok_or("Publication not found in catalog")?;
+        let language_code = publication
+            .language_code()
+            .ok_or("Couldn't tell the language of the publication")?;
+        let document = catalog
+            .get_publication(filename_symbol)?
+            .get_document_by_id(document_id)?
+            .ok_or("Document not found in publication")?;
+
+        self.get_document_media(document.meps_document_id, language_code)
+    }
+
+    fn lookup(
+        &self,
+        key: String,
+        query: &PubMediaQuery,
+        language_code: &str,
+    ) -> Result<Arc<Vec<MediaRecording>>, Box<dyn std::error::Error>> {
+        if let Some((resolved_at, recordings)) = self
+            .links
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            if resolved_at.elapsed() < LINKS_MAX_AGE {
+                return Ok(recordings.clone());
+            }
+        }
+
+        let mut links =
+            self.remote
+                .get_pub_media_links(query, language_code, &[AUDIO_FORMAT, VIDEO_FORMAT])?;
+        let mut recordings = group_recordings(
+            MediaKind::Audio,
+            links.remove(AUDIO_FORMAT).unwrap_or_default(),
+        );
+        recordings.extend(group_recordings(
+            MediaKind::Video,
+            links.remove(VIDEO_FORMAT).unwrap_or_default(),
+        ));
+        recordings.sort_by_key(|recording| (recording.booknum, recording.track, recording.kind));
+
+        let recordings = Arc::new(recordings);
+        self.links
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(key, (Instant::now(), recordings.clone()));
+
+        Ok(recordings)
+    }
+
+    /// Path of the cached catalog file `name`, downloading it when missing,
+    /// stale or `refresh` is set. A stale file is still used when the
+    /// download fails.
+    fn cached_file(
+        &self,
+        name: &str,
+        refresh: bool,
+    ) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = self.cache_path.join(name);
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+        if !refresh && matches!(age, Some(age) if age < CATALOG_MAX_AGE) {
+            return Ok(path);
+        }
+
+        // Catalog files change in place, a previous partial one can't be resumed
+        let _ = fs::remove_file(partial_path_of(&path));
+        let url = format!("{}/{}/{}", self.remote.base_url(), MEDIA_CATALOG_PATH, name);
+        let downloaded = self.downloader.download(
+            DownloadRequest {
+                kind: DownloadKind::Catalog,
+                label: format!("media catalog {}", name),
+                url,
+                destination: path.clone(),
+                expected: ExpectedFile::default(),
+            },
+            |_| {},
+        );
+
+        match downloaded {
+            Ok(()) => Ok(path),
+            Err(err) if age.is_some() => {
+                warn!(target: TARGET, "Couldn't refresh media catalog {}, using the cached one: {}", name, err);
+                Ok(path)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn parse_language_catalog(path: &Path) -> Result<MediaLanguageCatalog, Box<dyn std::error::Error>> {
+    let reader = io::BufReader::new(GzDecoder::new(fs::File::open(path)?));
+    let mut catalog = MediaLanguageCatalog::default();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Records of other types (schema version, language) aren't needed
+        let record: MediaCatalogRecord = serde_json::from_str(&line)?;
+        match record.record_type.as_str() {
+            "category" => match serde_json::from_value(record.o) {
+                Ok(category) => catalog.categories.push(category),
+                Err(err) => warn!(target: TARGET, "Skipping invalid media category: {}", err),
+            },
+            "media-item" => match serde_json::from_value(record.o) {
+                Ok(item) => catalog.items.push(item),
+                Err(err) => warn!(target: TARGET, "Skipping invalid media item: {}", err),
+            },
+            _ => {}
+        }
+    }
+
+    Ok(catalog)
+}
+
+/// Groups the files of a format by track and document, one variant per
+/// quality, lowest first.
+fn group_recordings(kind: MediaKind, files: Vec<PubMediaFile>) -> Vec<MediaRecording> {
+    let mut recordings: Vec<MediaRecording> = Vec::new();
+    let mut indexes: HashMap<(Option<i32>, Option<i32>, Option<i32>), usize> = HashMap::new();
+
+    for file in files {
+        let variant = MediaVariant {
+            label: file.label,
+            url: file.file.url,
+            checksum: file.file.checksum,
+            filesize: file.filesize,
+            mimetype: file.mimetype,
+            bit_rate: file.bit_rate,
+            frame_height: file.frame_height,
+        };
+
+        let key = (file.booknum, file.track, file.docid);
+        match indexes.get(&key) {
+            Some(index) => recordings[*index].variants.push(variant),
+            None => {
+                indexes.insert(key, recordings.len());
+                recordings.push(MediaRecording {
+                    kind,
+                    title: file.title,
+                    track: file.track,
+                    docid: file.docid,
+                    booknum: file.booknum,
+                    duration: file.duration,
+                    variants: vec![variant],
+                });
+            }
+        }
+    }
+
+    for recording in &mut recordings {
+        recording
+            .variants
+            .sort_by_key(|variant| (variant.frame_height, variant.filesize));
+    }
+
+    recordings
+}
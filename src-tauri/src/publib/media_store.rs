@@ -0,0 +1,497 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use colored::Colorize;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    download::{
+        verify_file, DownloadEvent, DownloadKind, DownloadManager, DownloadRequest, ExpectedFile,
+    },
+    media::{MediaKind, MediaRecording},
+    pool::open_connection,
+    Catalog,
+};
+
+const TARGET: &'static str = "catalog::media_store";
+
+// Setting key of the quota, in bytes
+const QUOTA_SETTING: &'static str = "Quota";
+
+// Columns read by `StoredMedia::from_row`
+const STORED_MEDIA_QUERY: &'static str = "SELECT
+    StoredMediaId,
+    Kind,
+    PublicationSymbol,
+    MepsDocumentId,
+    BookNumber,
+    Track,
+    LanguageCode,
+    Title,
+    Label,
+    Url,
+    FilePath,
+    MimeType,
+    Size,
+    Checksum,
+    Duration,
+    DownloadedAt
+FROM StoredMedia";
+
+/// Audio or video file downloaded for offline use.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StoredMedia {
+    pub id: i64,
+    pub kind: MediaKind,
+    /// Filename symbol of the publication the media belongs to.
+    pub publication_symbol: String,
+    pub meps_document_id: Option<i32>,
+    pub booknum: Option<i32>,
+    pub track: Option<i32>,
+    pub language_code: String,
+    pub title: Option<String>,
+    pub label: Option<String>,
+    pub url: String,
+    /// Location inside of the media directory, served at
+    /// `jwpub-media://localhost/media/{file_path}`.
+    pub file_path: String,
+    pub mime_type: Option<String>,
+    pub size: u64,
+    pub checksum: Option<String>,
+    pub duration: Option<f64>,
+    pub downloaded_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MediaStoreUsage {
+    pub used: u64,
+    pub quota: Option<u64>,
+}
+
+impl StoredMedia {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let kind: String = row.get(1)?;
+        Ok(Self {
+            id: row.get(0)?,
+            kind: match kind.as_str() {
+                "video" => MediaKind::Video,
+                _ => MediaKind::Audio,
+            },
+            publication_symbol: row.get(2)?,
+            meps_document_id: row.get(3)?,
+            booknum: row.get(4)?,
+            track: row.get(5)?,
+            language_code: row.get(6)?,
+            title: row.get(7)?,
+            label: row.get(8)?,
+            url: row.get(9)?,
+            file_path: row.get(10)?,
+            mime_type: row.get(11)?,
+            size: row.get(12)?,
+            checksum: row.get(13)?,
+            duration: row.get(14)?,
+            downloaded_at: row.get(15)?,
+        })
+    }
+}
+
+/// Audio and video files kept for offline use, tracked in `media.db` next
+/// to the catalog `collections.db`.
+pub struct MediaStore {
+    media_path: PathBuf,
+    db: Mutex<Connection>,
+    downloader: Arc<DownloadManager>,
+    // Bytes of the downloads in progress, counted against the quota
+    reserved: Mutex<u64>,
+}
+
+impl MediaStore {
+    pub fn init<T: Into<PathBuf>>(
+        location: T,
+        downloader: Arc<DownloadManager>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let location: PathBuf = location.into();
+        let media_path = location.join("media");
+        if !media_path.exists() {
+            fs::create_dir_all(&media_path)?;
+        }
+
+        let db = open_connection(location.join("media.db"), OpenFlags::default())?;
+        db.pragma_update(None, "journal_mode", "WAL")?;
+
+        debug!(target: TARGET, "initializing \"{}\" table...", "StoredMedia".magenta());
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS StoredMedia (
+                StoredMediaId INTEGER PRIMARY KEY AUTOINCREMENT,
+                Kind TEXT NOT NULL,
+
+                PublicationSymbol TEXT NOT NULL,
+                MepsDocumentId INTEGER,
+                BookNumber INTEGER,
+                Track INTEGER,
+                LanguageCode TEXT NOT NULL,
+
+                Title TEXT,
+                Label TEXT,
+
+                Url TEXT NOT NULL,
+                FilePath TEXT NOT NULL,
+                MimeType TEXT,
+                Size INTEGER NOT NULL,
+                Checksum TEXT,
+                Duration REAL,
+                DownloadedAt TEXT NOT NULL,
+
+                -- Publications sharing a recording share its file
+                UNIQUE(PublicationSymbol, FilePath)
+            )",
+            (),
+        )?;
+
+        debug!(target: TARGET, "initializing \"{}\" table...", "Setting".magenta());
+        db.execute(
+            "CREATE TABLE IF NOT EXISTS Setting (
+                Key TEXT PRIMARY KEY,
+                Value TEXT NOT NULL
+            )",
+            (),
+        )?;
+
+        let store = Self {
+            media_path,
+            db: Mutex::new(db),
+            downloader,
+            reserved: Mutex::new(0),
+        };
+        store.prune()?;
+
+        Ok(store)
+    }
+
+    /// Directory the media files are stored in.
+    pub fn media_path(&self) -> &Path {
+        &self.media_path
+    }
+
+    /// Resolves `file_path` inside the media directory, if it was
+    /// downloaded. Paths escaping that directory aren't resolved.
+    pub fn get_media_file_path(&self, file_path: &str) -> Option<PathBuf> {
+        let file_path = Path::new(file_path);
+        if !file_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+
+        Some(self.media_path.join(file_path)).filter(|path| path.is_file())
+    }
+
+    /// Lists stored media, optionally only of a publication and document.
+    pub fn get_media(
+        &self,
+        publication_symbol: Option<&str>,
+        meps_document_id: Option<i32>,
+    ) -> Result<Vec<StoredMedia>, Box<dyn std::error::Error>> {
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stmt = db.prepare(&format!(
+            "{}
+            WHERE (?1 IS NULL OR PublicationSymbol = ?1) AND
+                (?2 IS NULL OR MepsDocumentId = ?2)
+            ORDER BY PublicationSymbol, BookNumber, Track, MepsDocumentId",
+            STORED_MEDIA_QUERY
+        ))?;
+
+        let media = stmt
+            .query_map(params![publication_symbol, meps_document_id], |row| {
+                StoredMedia::from_row(row)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(media)
+    }
+
+    pub fn usage(&self) -> Result<MediaStoreUsage, Box<dyn std::error::Error>> {
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        let used: i64 = db.query_row(
+            "SELECT COALESCE(SUM(Size), 0) FROM (
+                SELECT MAX(Size) AS Size FROM StoredMedia GROUP BY FilePath
+            )",
+            [],
+            |row| row.get(0),
+        )?;
+        let quota: Option<String> = db
+            .query_row(
+                "SELECT Value FROM Setting WHERE Key = ?1",
+                [QUOTA_SETTING],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(MediaStoreUsage {
+            used: used as u64,
+            quota: quota.and_then(|quota| quota.parse().ok()),
+        })
+    }
+
+    /// Limits the disk space taken by stored media. `None` removes the
+    /// limit. Media already stored is kept even when over the new quota.
+    pub fn set_quota(&self, quota: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+        info!(target: TARGET, "Media quota set to {}.", quota.map(|quota| quota.to_string()).unwrap_or("unlimited".to_owned()).yellow());
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        match quota {
+            Some(quota) => db.execute(
+                "INSERT INTO Setting (Key, Value) VALUES (?1, ?2)
+                ON CONFLICT(Key) DO UPDATE SET Value = excluded.Value",
+                params![QUOTA_SETTING, quota.to_string()],
+            )?,
+            None => db.execute("DELETE FROM Setting WHERE Key = ?1", [QUOTA_SETTING])?,
+        };
+        Ok(())
+    }
+
+    /// Downloads a recording of an installed publication for offline use,
+    /// in the quality `label` or the lowest one available.
+    pub fn download(
+        &self,
+        catalog: &Catalog,
+        filename_symbol: &str,
+        meps_document_id: Option<i32>,
+        recording: &MediaRecording,
+        label: Option<&str>,
+        on_event: impl FnMut(&DownloadEvent),
+    ) -> Result<StoredMedia, Box<dyn std::error::Error>> {
+        let publication = catalog
+            .get_publication_collection_meta(filename_symbol)?
+            .ok_or("Publication not found in catalog")?;
+        let language_code = publication
+            .language_code()
+            .ok_or("Couldn't tell the language of the publication")?
+            .to_owned();
+
+        let variant = match label {
+            Some(label) => recording
+                .variants
+                .iter()
+                .find(|variant| variant.label.as_deref() == Some(label))
+                .ok_or(format!("Media isn't available in {}", label))?,
+            None => recording.variants.first().ok_or("Media has no files")?,
+        };
+
+        let filename = variant
+            .url
+            .rsplit('/')
+            .next()
+            .filter(|filename| !filename.is_empty())
+            .ok_or("Media URL has no file name")?;
+        let file_path = format!("{}/{}", language_code, filename);
+        let destination = self
+            .get_safe_path(&file_path)
+            .ok_or("Media URL has an invalid file name")?;
+
+        let expected = ExpectedFile {
+            size: Some(variant.filesize),
+            md5: variant.checksum.clone(),
+        };
+        if self.is_stored(&file_path)? && verify_file(&destination, &expected).is_ok() {
+            debug!(target: TARGET, "Media {} is already stored, adding it to \"{}\".", file_path, filename_symbol);
+        } else {
+            self.reserve(variant.filesize)?;
+            info!(target: TARGET, "Downloading media {} for \"{}\"...", file_path.green(), filename_symbol.bright_magenta());
+            let downloaded = self.downloader.download(
+                DownloadRequest {
+                    kind: DownloadKind::Media,
+                    label: recording
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| filename.to_owned()),
+                    url: variant.url.clone(),
+                    destination: destination.clone(),
+                    expected: expected.clone(),
+                },
+                on_event,
+            );
+            self.release(variant.filesize);
+            downloaded?;
+        }
+
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        db.execute(
+            "INSERT INTO StoredMedia (
+                Kind, PublicationSymbol, MepsDocumentId, BookNumber, Track,
+                LanguageCode, Title, Label, Url, FilePath, MimeType, Size,
+                Checksum, Duration, DownloadedAt
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+            ON CONFLICT(PublicationSymbol, FilePath) DO UPDATE SET
+                MepsDocumentId = excluded.MepsDocumentId,
+                BookNumber = excluded.BookNumber,
+                Track = excluded.Track,
+                Title = excluded.Title,
+                Label = excluded.Label,
+                Url = excluded.Url,
+                Size = excluded.Size,
+                Checksum = excluded.Checksum,
+                DownloadedAt = excluded.DownloadedAt",
+            params![
+                match recording.kind {
+                    MediaKind::Audio => "audio",
+                    MediaKind::Video => "video",
+                },
+                filename_symbol,
+                meps_document_id.or(recording.docid),
+                recording.booknum,
+                recording.track,
+                language_code,
+                recording.title,
+                variant.label,
+                variant.url,
+                file_path,
+                variant.mimetype,
+                variant.filesize,
+                variant.checksum,
+                recording.duration,
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )?;
+
+        Ok(db.query_row(
+            &format!(
+                "{} WHERE PublicationSymbol = ?1 AND FilePath = ?2",
+                STORED_MEDIA_QUERY
+            ),
+            [filename_symbol, &file_path],
+            |row| StoredMedia::from_row(row),
+        )?)
+    }
+
+    /// Deletes a stored media file. Returns `false` when it wasn't stored.
+    ///
+    /// The file stays on disk while other publications still use it.
+    pub fn delete(&self, id: i64) -> Result<bool, Box<dyn std::error::Error>> {
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        let file_path: Option<String> = db
+            .query_row(
+                "SELECT FilePath FROM StoredMedia WHERE StoredMediaId = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match file_path {
+            Some(file_path) => {
+                self.delete_stored(&db, id, &file_path)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Deletes every stored media file of a publication, returning how
+    /// many were deleted.
+    pub fn delete_publication_media(
+        &self,
+        publication_symbol: &str,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let media = self.get_media(Some(publication_symbol), None)?;
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        for stored in &media {
+            self.delete_stored(&db, stored.id, &stored.file_path)?;
+        }
+
+        info!(target: TARGET, "Deleted {} media files of \"{}\".", media.len().to_string().yellow(), publication_symbol.bright_magenta());
+        Ok(media.len())
+    }
+
+    fn is_stored(&self, file_path: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(db.query_row(
+            "SELECT EXISTS(SELECT 1 FROM StoredMedia WHERE FilePath = ?1)",
+            [file_path],
+            |row| row.get(0),
+        )?)
+    }
+
+    // Forgets a stored media, removing its file once no other
+    // publication uses it
+    fn delete_stored(
+        &self,
+        db: &Connection,
+        id: i64,
+        file_path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let shared: bool = db.query_row(
+            "SELECT EXISTS(
+                SELECT 1 FROM StoredMedia WHERE FilePath = ?1 AND StoredMediaId != ?2
+            )",
+            params![file_path, id],
+            |row| row.get(0),
+        )?;
+        if !shared {
+            self.remove_file(file_path)?;
+        }
+        db.execute("DELETE FROM StoredMedia WHERE StoredMediaId = ?1", [id])?;
+        Ok(())
+    }
+
+    fn get_safe_path(&self, file_path: &str) -> Option<PathBuf> {
+        let file_path = Path::new(file_path);
+        file_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+            .then(|| self.media_path.join(file_path))
+    }
+
+    fn remove_file(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self
+            .get_safe_path(file_path)
+            .ok_or("Stored media has an invalid path")?;
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts `size` against the quota until released, failing when it
+    /// doesn't fit.
+    fn reserve(&self, size: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let usage = self.usage()?;
+        let mut reserved = self.reserved.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(quota) = usage.quota {
+            if usage.used + *reserved + size > quota {
+                return Err(format!(
+                    "Not enough space in the media quota: {} bytes needed, {} available",
+                    size,
+                    quota.saturating_sub(usage.used + *reserved)
+                )
+                .into());
+            }
+        }
+
+        *reserved += size;
+        Ok(())
+    }
+
+    fn release(&self, size: u64) {
+        let mut reserved = self.reserved.lock().unwrap_or_else(PoisonError::into_inner);
+        *reserved = reserved.saturating_sub(size);
+    }
+
+    /// Forgets stored media whose file was removed from disk.
+    fn prune(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let media = self.get_media(None, None)?;
+        let db = self.db.lock().unwrap_or_else(PoisonError::into_inner);
+        for stored in media {
+            if self.get_media_file_path(&stored.file_path).is_none() {
+                warn!(target: TARGET, "Media file {} is missing, forgetting it.", stored.file_path);
+                db.execute(
+                    "DELETE FROM StoredMedia WHERE StoredMediaId = ?1",
+                    [stored.id],
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,5 +6,32 @@ pub use publication::Publication;
 
 pub mod tables;
 
+pub mod pool;
+pub use pool::ConnectionPool;
+
+pub mod dates;
+pub use dates::IssuePeriod;
+
 pub mod catalog;
 pub use catalog::Catalog;
+
+pub mod download;
+pub use download::DownloadManager;
+
+pub mod remote;
+pub use remote::RemoteCatalog;
+
+pub mod media;
+pub use media::MediaCatalog;
+
+pub mod media_store;
+pub use media_store::MediaStore;
+
+pub mod alerts;
+pub use alerts::AlertsClient;
+
+pub mod languages;
+pub use languages::LanguageRegistry;
+
+#[cfg(test)]
+mod test_server;
@@ -0,0 +1,101 @@
+use std::{
+    ops::Deref,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use rusqlite::{Connection, OpenFlags};
+
+// How long a connection waits for the writer to release the database
+// before failing with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Small pool of SQLite connections to the same database.
+///
+/// Connections are opened on demand and kept for reuse once released,
+/// up to `max_idle` of them, so readers on different threads never wait
+/// on each other.
+pub struct ConnectionPool {
+    path: PathBuf,
+    flags: OpenFlags,
+    max_idle: usize,
+    idle: Mutex<Vec<Connection>>,
+}
+
+pub struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    connection: Option<Connection>,
+}
+
+impl ConnectionPool {
+    pub fn new<T: Into<PathBuf>>(path: T, flags: OpenFlags, max_idle: usize) -> Self {
+        Self {
+            path: path.into(),
+            flags,
+            max_idle,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Opens pool connections in read-only mode.
+    pub fn read_only<T: Into<PathBuf>>(path: T, max_idle: usize) -> Self {
+        Self::new(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            max_idle,
+        )
+    }
+
+    pub fn get(&self) -> rusqlite::Result<PooledConnection<'_>> {
+        let idle = self
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+
+        let connection = match idle {
+            Some(connection) => connection,
+            None => open_connection(&self.path, self.flags)?,
+        };
+
+        Ok(PooledConnection {
+            pool: self,
+            connection: Some(connection),
+        })
+    }
+
+    fn release(&self, connection: Connection) {
+        let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        if idle.len() < self.max_idle {
+            idle.push(connection);
+        }
+    }
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.release(connection);
+        }
+    }
+}
+
+pub fn open_connection<T: AsRef<std::path::Path>>(
+    path: T,
+    flags: OpenFlags,
+) -> rusqlite::Result<Connection> {
+    let connection = Connection::open_with_flags(path, flags)?;
+    connection.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(connection)
+}
@@ -1,4 +1,10 @@
-use std::{fs, io::Read, num::NonZero, path::PathBuf};
+use std::{
+    fs,
+    io::Read,
+    num::NonZero,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+};
 
 use aes::{
     cipher::{generic_array::GenericArray, BlockDecryptMut, BlockSizeUser, KeyIvInit},
@@ -8,20 +14,24 @@ use cbc::Decryptor;
 use colored::Colorize;
 use inflate::inflate_bytes_zlib;
 use lru::LruCache;
-use rusqlite::Connection;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use super::tables::*;
+use super::{dates, pool::ConnectionPool, tables::*};
 
 const TARGET: &'static str = "catalog::publication";
 
+// Idle read connections kept open for each cached publication
+const MAX_IDLE_READERS: usize = 2;
+
 #[derive(Hash, Eq, PartialEq, Copy, Clone)]
 pub enum ContentTables {
     Document,
-    // Other aren't implemented
     DatedText,
-    Endnote,
     Extract,
+    // Other aren't implemented
+    Endnote,
     ExtractMultimedia,
     Multimedia,
     Footnote,
@@ -34,12 +44,179 @@ pub enum ContentTables {
     VerseCommentary,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct TopicDocumentReference {
+    pub document_id: i32,
+    pub meps_document_id: i32,
+    pub title: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicationTopic {
+    pub topic: Topic,
+    pub documents: Vec<TopicDocumentReference>,
+}
+
+// Summary of a `RefPublication` row, the publication quoted by an extract
+#[derive(Serialize, Deserialize)]
+pub struct ExtractSource {
+    pub id: i32,
+    pub title: String,
+    pub short_title: String,
+    pub symbol: String,
+    pub undated_symbol: String,
+    pub year: i32,
+    pub issue_tag_number: i32,
+    pub meps_language_index: i32,
+    pub publication_type: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicationExtract {
+    pub extract_id: i32,
+
+    // Paragraphs of the current document holding the extract
+    pub begin_paragraph_ordinal: Option<i32>,
+    pub end_paragraph_ordinal: Option<i32>,
+    pub sort_position: i32,
+
+    pub link: String,
+    pub caption: String,
+    pub caption_rich: Option<String>,
+
+    // Decrypted HTML content
+    pub content: Option<String>,
+
+    pub ref_meps_document_id: Option<i32>,
+    pub ref_meps_document_class: Option<i32>,
+    pub ref_begin_paragraph_ordinal: Option<i32>,
+    pub ref_end_paragraph_ordinal: Option<i32>,
+    pub source: Option<ExtractSource>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaAvailability {
+    // File is shipped inside the publication package
+    Bundled,
+    // File must be fetched using its key symbol, track or MEPS document
+    Remote,
+    Unavailable,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DocumentMedia {
+    pub multimedia_id: i32,
+    pub document_id: i32,
+
+    // Paragraph range of the document the media belongs to
+    pub begin_paragraph_ordinal: Option<i32>,
+    pub end_paragraph_ordinal: Option<i32>,
+
+    pub data_type: i32,
+    pub major_type: i32,
+    pub minor_type: i32,
+    pub category_type: i32,
+
+    pub mime_type: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+
+    pub label: Option<String>,
+    pub caption: Option<String>,
+    pub credit_line: Option<String>,
+
+    // Decrypted HTML contents
+    pub caption_content: Option<String>,
+    pub credit_line_content: Option<String>,
+
+    pub file_path: Option<String>,
+    pub key_symbol: Option<String>,
+    pub track: Option<i32>,
+    pub meps_document_id: Option<i32>,
+    pub meps_language_index: Option<i32>,
+    pub issue_tag_number: Option<i32>,
+
+    pub suppress_zoom: bool,
+    pub availability: MediaAvailability,
+}
+
+// Segment of the video an extract quotes, as marked by `ExtractVideoMarker`
+#[derive(Serialize, Deserialize)]
+pub struct ExtractVideoSegment {
+    pub extract_video_marker_id: i32,
+    pub extract_id: i32,
+
+    pub label: Option<String>,
+    pub caption: Option<String>,
+    pub style: Option<String>,
+    pub segment_format: i32,
+
+    pub start_time_ticks: i64,
+    pub duration_ticks: i64,
+    // Same as above, converted to seconds
+    pub start_time: f64,
+    pub duration: f64,
+
+    pub start_frame: i32,
+    pub frame_count: i32,
+
+    // Quoted document, whose video is fetched remotely
+    pub ref_meps_document_id: Option<i32>,
+    pub key_symbol: Option<String>,
+    pub meps_language_index: Option<i32>,
+    pub issue_tag_number: Option<i32>,
+    pub availability: MediaAvailability,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicationParagraph {
+    pub paragraph_index: i32,
+    // Printed paragraph number, if any
+    pub label: Option<i32>,
+    // Decrypted HTML content
+    pub content: Option<String>,
+}
+
+// .NET ticks used by video markers, 100 nanoseconds each
+const TICKS_PER_SECOND: f64 = 10_000_000.0;
+
+#[derive(Serialize, Deserialize)]
+pub struct VideoSegment {
+    pub video_marker_id: i32,
+    pub multimedia_id: i32,
+
+    pub label: Option<String>,
+    pub caption: Option<String>,
+    pub style: Option<String>,
+    pub segment_format: i32,
+
+    pub start_time_ticks: i64,
+    pub duration_ticks: i64,
+    // Same as above, converted to seconds
+    pub start_time: f64,
+    pub duration: f64,
+
+    pub start_frame: i32,
+    pub frame_count: i32,
+
+    // Referenced video
+    pub mime_type: Option<String>,
+    pub file_path: Option<String>,
+    pub key_symbol: Option<String>,
+    pub track: Option<i32>,
+    pub meps_document_id: Option<i32>,
+    pub meps_language_index: Option<i32>,
+    pub issue_tag_number: Option<i32>,
+    pub availability: MediaAvailability,
+}
+
 pub struct Publication {
     pub catalog_id: i64,
-    db: Connection,
+    db: ConnectionPool,
     master_key: Vec<u8>,
     path: PathBuf,
-    decrypted_content_cache: LruCache<(ContentTables, i32), String>,
+    decrypted_content_cache: Mutex<LruCache<(ContentTables, i32), String>>,
 }
 
 impl Publication {
@@ -47,7 +224,7 @@ impl Publication {
         database_path: PathBuf,
         id: i64,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let db = Connection::open(&database_path)?;
+        let db = ConnectionPool::read_only(&database_path, MAX_IDLE_READERS);
         let master_key: Vec<u8>;
 
         debug!(
@@ -55,7 +232,8 @@ impl Publication {
             "Forging master key for decryption jobs..."
         );
         {
-            let mut stmt = db.prepare(
+            let connection = db.get()?;
+            let mut stmt = connection.prepare(
                 "SELECT MepsLanguageIndex, Symbol, Year, IssueTagNumber FROM Publication",
             )?;
 
@@ -102,10 +280,14 @@ impl Publication {
             db,
             master_key,
             path: database_path.clone().parent().unwrap().to_path_buf(),
-            decrypted_content_cache: LruCache::new(NonZero::new(5).unwrap()),
+            decrypted_content_cache: Mutex::new(LruCache::new(NonZero::new(5).unwrap())),
         })
     }
 
+    pub fn get_multimedia_path(&self, filename: &str) -> PathBuf {
+        self.path.join(filename)
+    }
+
     pub fn get_multimedia_data(
         &self,
         filename: String,
@@ -121,9 +303,18 @@ impl Publication {
         Ok(data)
     }
 
+    pub fn has_table(&self, table_name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let db = self.db.get()?;
+        let mut stmt =
+            db.prepare("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1")?;
+
+        Ok(stmt.query_row([table_name], |row| row.get::<_, i32>(0))? > 0)
+    }
+
     pub fn get_view_items(&self) -> Result<Vec<PublicationViewItem>, Box<dyn std::error::Error>> {
         let mut fallback = false;
-        let mut stmt = match self.db.prepare(
+        let db = self.db.get()?;
+        let mut stmt = match db.prepare(
             "SELECT
             PublicationViewItemId,
             PublicationViewId,
@@ -142,7 +333,7 @@ impl Publication {
                     target: TARGET,
                     "Falling back to deprecated PublicationViewItem table."
                 );
-                self.db.prepare(
+                db.prepare(
                     "SELECT
                     PublicationViewItemId,
                     PublicationViewId,
@@ -192,7 +383,8 @@ impl Publication {
     pub fn get_view_items_documents(
         &self,
     ) -> Result<Vec<PublicationViewItemDocument>, Box<dyn std::error::Error>> {
-        let mut stmt = self.db.prepare(
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
             "SELECT 
             PublicationViewItemDocumentId,
             PublicationViewItemId,
@@ -216,9 +408,10 @@ impl Publication {
         Ok(documents)
     }
 
-    pub fn get_documents(&mut self) -> Result<Vec<Document>, Box<dyn std::error::Error>> {
+    pub fn get_documents(&self) -> Result<Vec<Document>, Box<dyn std::error::Error>> {
         let mut fallback = false;
-        let mut stmt = match self.db.prepare(
+        let db = self.db.get()?;
+        let mut stmt = match db.prepare(
             "SELECT
             DocumentId,
             PublicationId,
@@ -263,7 +456,7 @@ impl Publication {
                     target: TARGET,
                     "Falling back to deprecated Document table."
                 );
-                self.db.prepare(
+                db.prepare(
                     "SELECT
                     DocumentId,
                     PublicationId,
@@ -423,10 +616,11 @@ impl Publication {
     }
 
     pub fn get_document_content_by_id(
-        &mut self,
+        &self,
         id: i32,
     ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
-        let mut stmt = self.db.prepare(
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
             "SELECT
             Content
         FROM Document WHERE DocumentId = ?1",
@@ -443,11 +637,12 @@ impl Publication {
     }
 
     pub fn get_document_by_id(
-        &mut self,
+        &self,
         id: i32,
     ) -> Result<Option<Document>, Box<dyn std::error::Error>> {
         let mut fallback = false;
-        let mut stmt = match self.db.prepare(
+        let db = self.db.get()?;
+        let mut stmt = match db.prepare(
             "SELECT
             DocumentId,
             PublicationId,
@@ -491,7 +686,7 @@ impl Publication {
                     target: TARGET,
                     "Falling back to deprecated Document table."
                 );
-                self.db.prepare(
+                db.prepare(
                     "SELECT
                     DocumentId,
                     PublicationId,
@@ -643,9 +838,73 @@ impl Publication {
         Ok(document)
     }
 
-    pub fn get_dated_texts(&mut self) -> Result<Vec<DatedText>, Box<dyn std::error::Error>> {
+    pub fn get_dated_text_content_by_id(
+        &self,
+        id: i32,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+            Content
+        FROM DatedText WHERE DatedTextId = ?1",
+        )?;
+
+        let mut rows = stmt.query([id])?;
+
+        let mut content = None;
+        if let Some(row) = rows.next()? {
+            content = row.get(0)?
+        }
+
+        Ok(content)
+    }
+
+    pub fn get_extract_content_by_id(
+        &self,
+        id: i32,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+            Content
+        FROM Extract WHERE ExtractId = ?1",
+        )?;
+
+        let mut rows = stmt.query([id])?;
+
+        let mut content = None;
+        if let Some(row) = rows.next()? {
+            content = row.get(0)?
+        }
+
+        Ok(content)
+    }
+
+    pub fn get_document_id_by_meps_id(
+        &self,
+        meps_document_id: i32,
+    ) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+            DocumentId
+        FROM Document WHERE MepsDocumentId = ?1",
+        )?;
+
+        let mut rows = stmt.query([meps_document_id])?;
+
+        let mut document_id = None;
+        if let Some(row) = rows.next()? {
+            document_id = row.get(0)?
+        }
+
+        Ok(document_id)
+    }
+
+    pub fn get_dated_texts(&self) -> Result<Vec<DatedText>, Box<dyn std::error::Error>> {
         let mut fallback = false;
-        let mut stmt = match self.db.prepare(
+        let db = self.db.get()?;
+        let mut stmt = match db.prepare(
             "SELECT
             DatedTextId,
             DocumentId,
@@ -670,7 +929,7 @@ impl Publication {
                     target: TARGET,
                     "Falling back to deprecated DatedText table."
                 );
-                self.db.prepare(
+                db.prepare(
                     "SELECT
                     DatedTextId,
                     DocumentId,
@@ -691,12 +950,16 @@ impl Publication {
 
         let mut dated_texts = vec![];
         while let Some(row) = rows.next()? {
+            let first_date_offset: i32 = row.get(3)?;
+            let last_date_offset: i32 = row.get(4)?;
             let dated_text = DatedText {
                 id: row.get(0)?,
                 document_id: row.get(1)?,
                 link: row.get(2)?,
-                first_date_offset: row.get(3)?,
-                last_date_offset: row.get(4)?,
+                first_date_offset,
+                last_date_offset,
+                first_date: dates::date_from_offset(first_date_offset),
+                last_date: dates::date_from_offset(last_date_offset),
                 first_footnote_id: row.get(5)?,
                 last_footnote_id: row.get(6)?,
                 first_bible_citation_id: row.get(7)?,
@@ -729,16 +992,644 @@ impl Publication {
         Ok(dated_texts)
     }
 
+    /// Lists topics whose name matches `query`. An empty query lists
+    /// every topic on the publication.
+    pub fn get_topics(&self, query: &str) -> Result<Vec<Topic>, Box<dyn std::error::Error>> {
+        if !self.has_table("Topic")? {
+            return Ok(vec![]);
+        }
+
+        let mut fallback = false;
+        let db = self.db.get()?;
+        let mut stmt = match db.prepare(
+            "SELECT
+            TopicId,
+            Topic,
+            DisplayTopic,
+            DisplayTopicRich
+        FROM Topic WHERE Topic LIKE ?1 ESCAPE '\\' OR DisplayTopic LIKE ?1 ESCAPE '\\'
+        ORDER BY Topic",
+        ) {
+            Ok(stmt) => stmt,
+            Err(_err) => {
+                fallback = true;
+                info!(
+                    target: TARGET,
+                    "Falling back to deprecated Topic table."
+                );
+                db.prepare(
+                    "SELECT
+                    TopicId,
+                    Topic,
+                    DisplayTopic
+                FROM Topic WHERE Topic LIKE ?1 ESCAPE '\\' OR DisplayTopic LIKE ?1 ESCAPE '\\'
+                ORDER BY Topic",
+                )?
+            }
+        };
+        // `%` and `_` are matched literally
+        let pattern = query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let mut rows = stmt.query([format!("%{}%", pattern)])?;
+
+        let mut topics = vec![];
+        while let Some(row) = rows.next()? {
+            topics.push(Topic {
+                id: row.get(0)?,
+                topic: row.get(1)?,
+                display_topic: row.get(2)?,
+                display_topic_rich: match fallback {
+                    false => row.get(3)?,
+                    true => None,
+                },
+            });
+        }
+
+        Ok(topics)
+    }
+
+    /// Lists topics matching `query` together with the documents each one
+    /// points to.
+    pub fn get_topic_index(
+        &self,
+        query: &str,
+    ) -> Result<Vec<PublicationTopic>, Box<dyn std::error::Error>> {
+        let topics = self.get_topics(query)?;
+        if topics.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+            Document.DocumentId,
+            Document.MepsDocumentId,
+            Document.Title
+        FROM TopicDocument
+        INNER JOIN Document ON Document.DocumentId = TopicDocument.DocumentId
+        WHERE TopicDocument.TopicId = ?1",
+        )?;
+
+        let mut index = vec![];
+        for topic in topics {
+            let mut rows = stmt.query(params![topic.id])?;
+            let mut documents = vec![];
+            while let Some(row) = rows.next()? {
+                documents.push(TopicDocumentReference {
+                    document_id: row.get(0)?,
+                    meps_document_id: row.get(1)?,
+                    title: row.get(2)?,
+                });
+            }
+
+            index.push(PublicationTopic { topic, documents });
+        }
+
+        Ok(index)
+    }
+
+    /// Lists extracts (quotations from other publications) found on a
+    /// document, with their content decrypted.
+    pub fn get_document_extracts(
+        &self,
+        document_id: i32,
+    ) -> Result<Vec<PublicationExtract>, Box<dyn std::error::Error>> {
+        if !self.has_table("DocumentExtract")? {
+            return Ok(vec![]);
+        }
+
+        let mut fallback = false;
+        let mut extracts = vec![];
+        {
+            let db = self.db.get()?;
+            let mut stmt = match db.prepare(
+                "SELECT
+                Extract.ExtractId,
+                DocumentExtract.BeginParagraphOrdinal,
+                DocumentExtract.EndParagraphOrdinal,
+                DocumentExtract.SortPosition,
+                Extract.Link,
+                Extract.Caption,
+                Extract.RefMepsDocumentId,
+                Extract.RefMepsDocumentClass,
+                Extract.RefBeginParagraphOrdinal,
+                Extract.RefEndParagraphOrdinal,
+                RefPublication.RefPublicationId,
+                RefPublication.Title,
+                RefPublication.ShortTitle,
+                RefPublication.Symbol,
+                RefPublication.UndatedSymbol,
+                RefPublication.Year,
+                RefPublication.IssueTagNumber,
+                RefPublication.MepsLanguageIndex,
+                RefPublication.PublicationType,
+                Extract.CaptionRich
+            FROM DocumentExtract
+            INNER JOIN Extract ON Extract.ExtractId = DocumentExtract.ExtractId
+            LEFT JOIN RefPublication ON RefPublication.RefPublicationId = Extract.RefPublicationId
+            WHERE DocumentExtract.DocumentId = ?1
+            ORDER BY DocumentExtract.SortPosition",
+            ) {
+                Ok(stmt) => stmt,
+                Err(_err) => {
+                    fallback = true;
+                    info!(
+                        target: TARGET,
+                        "Falling back to deprecated Extract table."
+                    );
+                    db.prepare(
+                        "SELECT
+                        Extract.ExtractId,
+                        DocumentExtract.BeginParagraphOrdinal,
+                        DocumentExtract.EndParagraphOrdinal,
+                        DocumentExtract.SortPosition,
+                        Extract.Link,
+                        Extract.Caption,
+                        Extract.RefMepsDocumentId,
+                        Extract.RefMepsDocumentClass,
+                        Extract.RefBeginParagraphOrdinal,
+                        Extract.RefEndParagraphOrdinal,
+                        RefPublication.RefPublicationId,
+                        RefPublication.Title,
+                        RefPublication.ShortTitle,
+                        RefPublication.Symbol,
+                        RefPublication.UndatedSymbol,
+                        RefPublication.Year,
+                        RefPublication.IssueTagNumber,
+                        RefPublication.MepsLanguageIndex,
+                        RefPublication.PublicationType
+                    FROM DocumentExtract
+                    INNER JOIN Extract ON Extract.ExtractId = DocumentExtract.ExtractId
+                    LEFT JOIN RefPublication ON RefPublication.RefPublicationId = Extract.RefPublicationId
+                    WHERE DocumentExtract.DocumentId = ?1
+                    ORDER BY DocumentExtract.SortPosition",
+                    )?
+                }
+            };
+            let mut rows = stmt.query([document_id])?;
+
+            while let Some(row) = rows.next()? {
+                let source = match row.get::<_, Option<i32>>(10)? {
+                    Some(id) => Some(ExtractSource {
+                        id,
+                        title: row.get(11)?,
+                        short_title: row.get(12)?,
+                        symbol: row.get(13)?,
+                        undated_symbol: row.get(14)?,
+                        year: row.get(15)?,
+                        issue_tag_number: row.get(16)?,
+                        meps_language_index: row.get(17)?,
+                        publication_type: row.get(18)?,
+                    }),
+                    None => None,
+                };
+
+                extracts.push(PublicationExtract {
+                    extract_id: row.get(0)?,
+                    begin_paragraph_ordinal: row.get(1)?,
+                    end_paragraph_ordinal: row.get(2)?,
+                    sort_position: row.get(3)?,
+                    link: row.get(4)?,
+                    caption: row.get(5)?,
+                    caption_rich: match fallback {
+                        false => row.get(19)?,
+                        true => None,
+                    },
+                    content: None,
+                    ref_meps_document_id: row.get(6)?,
+                    ref_meps_document_class: row.get(7)?,
+                    ref_begin_paragraph_ordinal: row.get(8)?,
+                    ref_end_paragraph_ordinal: row.get(9)?,
+                    source,
+                });
+            }
+        }
+
+        for extract in extracts.iter_mut() {
+            extract.content =
+                self.get_content_text_from(ContentTables::Extract, extract.extract_id)?;
+        }
+
+        Ok(extracts)
+    }
+
+    pub fn get_related_documents(
+        &self,
+        document_id: i32,
+        relationship_type: Option<i32>,
+    ) -> Result<Vec<RelatedDocument>, Box<dyn std::error::Error>> {
+        if !self.has_table("RelatedDocument")? {
+            return Ok(vec![]);
+        }
+
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+            RelatedDocumentId,
+            DocumentId,
+            MepsDocumentId,
+            RelationshipType
+        FROM RelatedDocument
+        WHERE DocumentId = ?1 AND (?2 IS NULL OR RelationshipType = ?2)",
+        )?;
+        let mut rows = stmt.query(params![document_id, relationship_type])?;
+
+        let mut related_documents = vec![];
+        while let Some(row) = rows.next()? {
+            related_documents.push(RelatedDocument {
+                id: row.get(0)?,
+                document_id: row.get(1)?,
+                meps_document_id: row.get(2)?,
+                relationship_type: row.get(3)?,
+            });
+        }
+
+        Ok(related_documents)
+    }
+
+    /// Lists media attached to `document_id`, or to every document when
+    /// it isn't given, ordered by document and paragraph.
+    pub fn get_document_media(
+        &self,
+        document_id: Option<i32>,
+    ) -> Result<Vec<DocumentMedia>, Box<dyn std::error::Error>> {
+        if !self.has_table("DocumentMultimedia")? {
+            return Ok(vec![]);
+        }
+
+        let mut fallback = false;
+        let db = self.db.get()?;
+        let mut stmt = match db.prepare(
+            "SELECT
+            Multimedia.MultimediaId,
+            DocumentMultimedia.DocumentId,
+            DocumentMultimedia.BeginParagraphOrdinal,
+            DocumentMultimedia.EndParagraphOrdinal,
+            Multimedia.DataType,
+            Multimedia.MajorType,
+            Multimedia.MinorType,
+            Multimedia.CategoryType,
+            Multimedia.MimeType,
+            Multimedia.Width,
+            Multimedia.Height,
+            Multimedia.Label,
+            Multimedia.Caption,
+            Multimedia.CreditLine,
+            Multimedia.CaptionContent,
+            Multimedia.CreditLineContent,
+            Multimedia.FilePath,
+            Multimedia.KeySymbol,
+            Multimedia.Track,
+            Multimedia.MepsDocumentId,
+            Multimedia.MepsLanguageIndex,
+            Multimedia.IssueTagNumber,
+            Multimedia.SuppressZoom
+        FROM DocumentMultimedia
+        INNER JOIN Multimedia ON Multimedia.MultimediaId = DocumentMultimedia.MultimediaId
+        WHERE (?1 IS NULL OR DocumentMultimedia.DocumentId = ?1)
+        ORDER BY DocumentMultimedia.DocumentId, DocumentMultimedia.BeginParagraphOrdinal",
+        ) {
+            Ok(stmt) => stmt,
+            Err(_err) => {
+                fallback = true;
+                info!(
+                    target: TARGET,
+                    "Falling back to deprecated Multimedia table."
+                );
+                db.prepare(
+                    "SELECT
+                    Multimedia.MultimediaId,
+                    DocumentMultimedia.DocumentId,
+                    DocumentMultimedia.BeginParagraphOrdinal,
+                    DocumentMultimedia.EndParagraphOrdinal,
+                    Multimedia.DataType,
+                    Multimedia.MajorType,
+                    Multimedia.MinorType,
+                    Multimedia.CategoryType,
+                    Multimedia.MimeType,
+                    Multimedia.Width,
+                    Multimedia.Height,
+                    Multimedia.Label,
+                    Multimedia.Caption,
+                    Multimedia.CreditLine,
+                    Multimedia.CaptionContent,
+                    Multimedia.CreditLineContent,
+                    Multimedia.FilePath,
+                    Multimedia.KeySymbol,
+                    Multimedia.Track,
+                    Multimedia.MepsDocumentId,
+                    Multimedia.MepsLanguageIndex,
+                    Multimedia.IssueTagNumber
+                FROM DocumentMultimedia
+                INNER JOIN Multimedia ON Multimedia.MultimediaId = DocumentMultimedia.MultimediaId
+                WHERE (?1 IS NULL OR DocumentMultimedia.DocumentId = ?1)
+                ORDER BY DocumentMultimedia.DocumentId, DocumentMultimedia.BeginParagraphOrdinal",
+                )?
+            }
+        };
+        let mut rows = stmt.query(params![document_id])?;
+
+        let mut media = vec![];
+        while let Some(row) = rows.next()? {
+            let file_path: Option<String> = row.get(16)?;
+            let key_symbol: Option<String> = row.get(17)?;
+            let meps_document_id: Option<i32> = row.get(19)?;
+
+            let availability = self.media_availability(&file_path, &key_symbol, &meps_document_id);
+
+            media.push(DocumentMedia {
+                multimedia_id: row.get(0)?,
+                document_id: row.get(1)?,
+                begin_paragraph_ordinal: row.get(2)?,
+                end_paragraph_ordinal: row.get(3)?,
+                data_type: row.get(4)?,
+                major_type: row.get(5)?,
+                minor_type: row.get(6)?,
+                category_type: row.get(7)?,
+                mime_type: row.get(8)?,
+                width: row.get(9)?,
+                height: row.get(10)?,
+                label: row.get(11)?,
+                caption: row.get(12)?,
+                credit_line: row.get(13)?,
+                caption_content: match row.get::<_, Option<Vec<u8>>>(14)? {
+                    Some(content) if !content.is_empty() => Some(self.decrypt_content(content)?),
+                    _ => None,
+                },
+                credit_line_content: match row.get::<_, Option<Vec<u8>>>(15)? {
+                    Some(content) if !content.is_empty() => Some(self.decrypt_content(content)?),
+                    _ => None,
+                },
+                file_path,
+                key_symbol,
+                track: row.get(18)?,
+                meps_document_id,
+                meps_language_index: row.get(20)?,
+                issue_tag_number: row.get(21)?,
+                suppress_zoom: match fallback {
+                    false => row.get::<_, Option<bool>>(22)?.unwrap_or(false),
+                    true => false,
+                },
+                availability,
+            });
+        }
+
+        Ok(media)
+    }
+
+    fn media_availability(
+        &self,
+        file_path: &Option<String>,
+        key_symbol: &Option<String>,
+        meps_document_id: &Option<i32>,
+    ) -> MediaAvailability {
+        match file_path {
+            Some(file_path) if !file_path.is_empty() && self.path.join(file_path).exists() => {
+                MediaAvailability::Bundled
+            }
+            _ if key_symbol.is_some() || meps_document_id.is_some() => MediaAvailability::Remote,
+            _ => MediaAvailability::Unavailable,
+        }
+    }
+
+    /// Lists video segments signing a document, or only its paragraph
+    /// `paragraph_index` when given.
+    pub fn get_paragraph_video_segments(
+        &self,
+        document_id: i32,
+        paragraph_index: Option<i32>,
+    ) -> Result<Vec<VideoSegment>, Box<dyn std::error::Error>> {
+        if !self.has_table("VideoMarkerParagraphLocation")? {
+            return Ok(vec![]);
+        }
+
+        self.get_video_segments(
+            "FROM VideoMarkerParagraphLocation
+            INNER JOIN DocumentParagraph ON DocumentParagraph.DocumentParagraphId = VideoMarkerParagraphLocation.DocumentParagraphId
+            INNER JOIN VideoMarker ON VideoMarker.VideoMarkerId = VideoMarkerParagraphLocation.VideoMarkerId
+            INNER JOIN Multimedia ON Multimedia.MultimediaId = VideoMarker.MultimediaId
+            WHERE DocumentParagraph.DocumentId = ?1 AND
+                (?2 IS NULL OR DocumentParagraph.ParagraphIndex = ?2)
+            ORDER BY DocumentParagraph.ParagraphIndex, VideoMarker.StartTimeTicks",
+            params![document_id, paragraph_index],
+        )
+    }
+
+    /// Lists video segments signing the Bible verse `bible_verse_id`.
+    pub fn get_verse_video_segments(
+        &self,
+        bible_verse_id: i32,
+    ) -> Result<Vec<VideoSegment>, Box<dyn std::error::Error>> {
+        if !self.has_table("VideoMarkerBibleVerseLocation")? {
+            return Ok(vec![]);
+        }
+
+        self.get_video_segments(
+            "FROM VideoMarkerBibleVerseLocation
+            INNER JOIN VideoMarker ON VideoMarker.VideoMarkerId = VideoMarkerBibleVerseLocation.VideoMarkerId
+            INNER JOIN Multimedia ON Multimedia.MultimediaId = VideoMarker.MultimediaId
+            WHERE VideoMarkerBibleVerseLocation.BibleVerseId = ?1
+            ORDER BY VideoMarker.StartTimeTicks",
+            params![bible_verse_id],
+        )
+    }
+
+    /// Lists video segments quoted by the extract `extract_id`.
+    pub fn get_extract_video_segments(
+        &self,
+        extract_id: i32,
+    ) -> Result<Vec<ExtractVideoSegment>, Box<dyn std::error::Error>> {
+        if !self.has_table("ExtractVideoMarker")? {
+            return Ok(vec![]);
+        }
+
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+            ExtractVideoMarker.ExtractVideoMarkerId,
+            ExtractVideoMarker.ExtractId,
+            ExtractVideoMarker.Label,
+            ExtractVideoMarker.Caption,
+            ExtractVideoMarker.Style,
+            ExtractVideoMarker.SegmentFormat,
+            ExtractVideoMarker.StartTimeTicks,
+            ExtractVideoMarker.DurationTicks,
+            ExtractVideoMarker.StartFrame,
+            ExtractVideoMarker.FrameCount,
+            Extract.RefMepsDocumentId,
+            RefPublication.UndatedSymbol,
+            RefPublication.MepsLanguageIndex,
+            RefPublication.IssueTagNumber
+        FROM ExtractVideoMarker
+        INNER JOIN Extract ON Extract.ExtractId = ExtractVideoMarker.ExtractId
+        LEFT JOIN RefPublication ON RefPublication.RefPublicationId = Extract.RefPublicationId
+        WHERE ExtractVideoMarker.ExtractId = ?1
+        ORDER BY ExtractVideoMarker.StartTimeTicks",
+        )?;
+        let mut rows = stmt.query([extract_id])?;
+
+        let mut segments = vec![];
+        while let Some(row) = rows.next()? {
+            let start_time_ticks: i64 = row.get(6)?;
+            let duration_ticks: i64 = row.get(7)?;
+            let ref_meps_document_id: Option<i32> = row.get(10)?;
+            let key_symbol: Option<String> = row.get(11)?;
+
+            segments.push(ExtractVideoSegment {
+                extract_video_marker_id: row.get(0)?,
+                extract_id: row.get(1)?,
+                label: row.get(2)?,
+                caption: row.get(3)?,
+                style: row.get(4)?,
+                segment_format: row.get(5)?,
+                start_time_ticks,
+                duration_ticks,
+                start_time: start_time_ticks as f64 / TICKS_PER_SECOND,
+                duration: duration_ticks as f64 / TICKS_PER_SECOND,
+                start_frame: row.get(8)?,
+                frame_count: row.get(9)?,
+                availability: self.media_availability(&None, &key_symbol, &ref_meps_document_id),
+                ref_meps_document_id,
+                key_symbol,
+                meps_language_index: row.get(12)?,
+                issue_tag_number: row.get(13)?,
+            });
+        }
+
+        Ok(segments)
+    }
+
+    fn get_video_segments<P: rusqlite::Params>(
+        &self,
+        from_clause: &str,
+        params: P,
+    ) -> Result<Vec<VideoSegment>, Box<dyn std::error::Error>> {
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(&format!(
+            "SELECT
+            VideoMarker.VideoMarkerId,
+            VideoMarker.MultimediaId,
+            VideoMarker.Label,
+            VideoMarker.Caption,
+            VideoMarker.Style,
+            VideoMarker.SegmentFormat,
+            VideoMarker.StartTimeTicks,
+            VideoMarker.DurationTicks,
+            VideoMarker.StartFrame,
+            VideoMarker.FrameCount,
+            Multimedia.MimeType,
+            Multimedia.FilePath,
+            Multimedia.KeySymbol,
+            Multimedia.Track,
+            Multimedia.MepsDocumentId,
+            Multimedia.MepsLanguageIndex,
+            Multimedia.IssueTagNumber
+        {}",
+            from_clause
+        ))?;
+        let mut rows = stmt.query(params)?;
+
+        let mut segments = vec![];
+        while let Some(row) = rows.next()? {
+            let start_time_ticks: i64 = row.get(6)?;
+            let duration_ticks: i64 = row.get(7)?;
+            let file_path: Option<String> = row.get(11)?;
+            let key_symbol: Option<String> = row.get(12)?;
+            let meps_document_id: Option<i32> = row.get(14)?;
+
+            segments.push(VideoSegment {
+                video_marker_id: row.get(0)?,
+                multimedia_id: row.get(1)?,
+                label: row.get(2)?,
+                caption: row.get(3)?,
+                style: row.get(4)?,
+                segment_format: row.get(5)?,
+                start_time_ticks,
+                duration_ticks,
+                start_time: start_time_ticks as f64 / TICKS_PER_SECOND,
+                duration: duration_ticks as f64 / TICKS_PER_SECOND,
+                start_frame: row.get(8)?,
+                frame_count: row.get(9)?,
+                mime_type: row.get(10)?,
+                availability: self.media_availability(&file_path, &key_symbol, &meps_document_id),
+                file_path,
+                key_symbol,
+                track: row.get(13)?,
+                meps_document_id,
+                meps_language_index: row.get(15)?,
+                issue_tag_number: row.get(16)?,
+            });
+        }
+
+        Ok(segments)
+    }
+
+    /// Lists the paragraphs of a document by ordinal, each with its slice
+    /// of the decrypted document content.
+    pub fn get_document_paragraphs(
+        &self,
+        document_id: i32,
+    ) -> Result<Vec<PublicationParagraph>, Box<dyn std::error::Error>> {
+        if !self.has_table("DocumentParagraph")? {
+            return Ok(vec![]);
+        }
+
+        let content = self
+            .get_content_text_from(ContentTables::Document, document_id)?
+            .unwrap_or_default();
+
+        let db = self.db.get()?;
+        let mut stmt = db.prepare(
+            "SELECT
+            ParagraphIndex,
+            ParagraphNumberLabel,
+            BeginPosition,
+            EndPosition
+        FROM DocumentParagraph WHERE DocumentId = ?1
+        ORDER BY ParagraphIndex",
+        )?;
+        let mut rows = stmt.query([document_id])?;
+
+        let mut paragraphs = vec![];
+        while let Some(row) = rows.next()? {
+            let begin_position: Option<i32> = row.get(2)?;
+            let end_position: Option<i32> = row.get(3)?;
+
+            // Positions are byte offsets in the decrypted content
+            let content = match (begin_position, end_position) {
+                (Some(begin), Some(end)) if begin >= 0 && end >= begin => content
+                    .get(begin as usize..end as usize)
+                    .map(|content| content.to_owned()),
+                _ => None,
+            };
+
+            paragraphs.push(PublicationParagraph {
+                paragraph_index: row.get(0)?,
+                label: row.get(1)?,
+                content,
+            });
+        }
+
+        Ok(paragraphs)
+    }
+
     // This function is quite inefficient in terms of memory, since it
     // save a Document on frontend and backend, but anyway the `LruCache`
     // save some processing power, especially when we need to go to the
     // next or previous chapter multiple times.
     pub fn get_content_text_from(
-        &mut self,
+        &self,
         content_table: ContentTables,
         id: i32,
     ) -> Result<Option<String>, Box<dyn std::error::Error>> {
-        if let Some(content) = self.decrypted_content_cache.get(&(content_table, id)) {
+        if let Some(content) = self
+            .decrypted_content_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(content_table, id))
+        {
             return Ok(Some(content.clone()));
         }
 
@@ -747,12 +1638,38 @@ impl Publication {
                 if let Some(content) = self.get_document_content_by_id(id)? {
                     let content = self.decrypt_content(content)?;
                     self.decrypted_content_cache
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
                         .put((ContentTables::Document, id), content.clone());
                     Ok(Some(content))
                 } else {
                     Ok(None)
                 }
             }
+            ContentTables::DatedText => {
+                if let Some(content) = self.get_dated_text_content_by_id(id)? {
+                    let content = self.decrypt_content(content)?;
+                    self.decrypted_content_cache
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .put((ContentTables::DatedText, id), content.clone());
+                    Ok(Some(content))
+                } else {
+                    Ok(None)
+                }
+            }
+            ContentTables::Extract => {
+                if let Some(content) = self.get_extract_content_by_id(id)? {
+                    let content = self.decrypt_content(content)?;
+                    self.decrypted_content_cache
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .put((ContentTables::Extract, id), content.clone());
+                    Ok(Some(content))
+                } else {
+                    Ok(None)
+                }
+            }
             _ => Err("Unsupported content table or not implemented yet".into()),
         }
     }
@@ -0,0 +1,1193 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+};
+
+use chrono::{DateTime, NaiveDateTime};
+use colored::Colorize;
+use flate2::read::GzDecoder;
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    catalog::CollectionPublication,
+    download::{DownloadKind, DownloadManager, DownloadRequest, ExpectedFile, JobId},
+    pool::ConnectionPool,
+    Catalog,
+};
+
+const TARGET: &'static str = "catalog::remote";
+
+pub const DEFAULT_BASE_URL: &'static str = "https://app.jw-cdn.org";
+pub const DEFAULT_PUB_MEDIA_BASE_URL: &'static str = "https://b.jw-cdn.org";
+const CATALOG_PATH: &'static str = "catalogs/publications/v4";
+
+// Columns read by `RemotePublication::from_row`, joined with the latest asset
+const PUBLICATION_QUERY: &'static str = "SELECT
+    Publication.Id,
+    Publication.MepsLanguageId,
+    PublicationType.Name,
+    Publication.IssueTagNumber,
+    Publication.Title,
+    Publication.ShortTitle,
+    Publication.IssueTitle,
+    Publication.CoverTitle,
+    Publication.UndatedTitle,
+    Publication.Year,
+    Publication.Symbol,
+    Publication.KeySymbol,
+    PublicationAsset.Size,
+    PublicationAsset.ExpandedSize,
+    PublicationAsset.Signature,
+    PublicationAsset.LastModified
+FROM Publication
+INNER JOIN PublicationType ON PublicationType.Id = Publication.PublicationTypeId
+LEFT JOIN PublicationAsset ON PublicationAsset.Id = (
+    SELECT Id FROM PublicationAsset
+    WHERE PublicationAsset.PublicationId = Publication.Id
+    ORDER BY LastModified DESC
+    LIMIT 1
+)";
+
+// Idle read connections kept open for the remote catalog database
+const MAX_IDLE_READERS: usize = 2;
+
+/// `manifest.json` of the publication catalog, pointing to the current
+/// catalog build.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RemoteManifest {
+    pub version: i32,
+    pub current: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RemoteLanguage {
+    pub meps_language_id: i32,
+    pub publication_count: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RemotePublicationType {
+    pub name: String,
+    pub publication_count: i32,
+}
+
+/// Publication available on the remote catalog, with its latest asset.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RemotePublication {
+    pub id: i32,
+    pub meps_language_id: i32,
+    pub publication_type: String,
+    pub issue_tag_number: i32,
+
+    pub title: String,
+    pub short_title: Option<String>,
+    pub issue_title: Option<String>,
+    pub cover_title: Option<String>,
+    pub undated_title: Option<String>,
+
+    pub year: i32,
+    pub symbol: String,
+    pub key_symbol: Option<String>,
+
+    // Latest `PublicationAsset` of the publication, if any
+    pub size: Option<i64>,
+    pub expanded_size: Option<i64>,
+    pub signature: Option<String>,
+    pub last_modified: Option<String>,
+
+    pub installed: bool,
+}
+
+impl RemotePublication {
+    // Expects the column order used by `get_publications`.
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            meps_language_id: row.get(1)?,
+            publication_type: row.get(2)?,
+            issue_tag_number: row.get::<_, Option<i32>>(3)?.unwrap_or(0),
+            title: row.get(4)?,
+            short_title: row.get(5)?,
+            issue_title: row.get(6)?,
+            cover_title: row.get(7)?,
+            undated_title: row.get(8)?,
+            year: row.get::<_, Option<i32>>(9)?.unwrap_or(0),
+            symbol: row.get(10)?,
+            key_symbol: row.get(11)?,
+            size: row.get(12)?,
+            expanded_size: row.get(13)?,
+            signature: row.get(14)?,
+            last_modified: row.get(15)?,
+            installed: false,
+        })
+    }
+
+    /// Whether `publication` is an installed copy of this publication.
+    pub fn matches(&self, publication: &CollectionPublication) -> bool {
+        let issue_tag_number = publication.issue_tag_number.unwrap_or(0);
+        publication.language_index == self.meps_language_id
+            && issue_tag_number == self.issue_tag_number
+            && (publication.symbol == self.symbol
+                || (self.issue_tag_number != 0
+                    && self.key_symbol.as_deref() == Some(publication.key_symbol.as_str())))
+    }
+}
+
+impl RemotePublication {
+    /// `pub` and `issue` parameters identifying the publication on the
+    /// pub-media API.
+    fn pub_media_query(&self) -> (&str, Option<i32>) {
+        pub_media_query(
+            &self.symbol,
+            self.key_symbol.as_deref(),
+            self.issue_tag_number,
+        )
+    }
+
+    // Whether `file` is the asset the catalog lists, as far as its size
+    // tells
+    fn describes(&self, file: &PubMediaFile) -> bool {
+        self.size.map_or(true, |size| size as u64 == file.filesize)
+    }
+}
+
+/// `pub` and `issue` parameters of a publication on the pub-media API.
+pub fn pub_media_query<'a>(
+    symbol: &'a str,
+    key_symbol: Option<&'a str>,
+    issue_tag_number: i32,
+) -> (&'a str, Option<i32>) {
+    if issue_tag_number == 0 {
+        return (symbol, None);
+    }
+
+    let symbol = key_symbol
+        .filter(|key_symbol| !key_symbol.is_empty())
+        .unwrap_or(symbol);
+    // Monthly issues are requested as `YYYYMM`, semimonthly as `YYYYMMDD`
+    match issue_tag_number % 100 {
+        0 => (symbol, Some(issue_tag_number / 100)),
+        _ => (symbol, Some(issue_tag_number)),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PubMediaFileLink {
+    pub url: String,
+    pub checksum: Option<String>,
+    #[serde(rename = "modifiedDatetime")]
+    pub modified_datetime: Option<String>,
+}
+
+/// File entry of a `GETPUBMEDIALINKS` response.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PubMediaFile {
+    pub title: Option<String>,
+    pub file: PubMediaFileLink,
+    pub filesize: u64,
+    pub mimetype: Option<String>,
+    pub track: Option<i32>,
+    pub docid: Option<i32>,
+    pub booknum: Option<i32>,
+    /// Quality label, e.g. `720p`.
+    pub label: Option<String>,
+    /// Length in seconds.
+    pub duration: Option<f64>,
+    #[serde(rename = "bitRate")]
+    pub bit_rate: Option<f64>,
+    #[serde(rename = "frameHeight")]
+    pub frame_height: Option<i32>,
+}
+
+/// What `GETPUBMEDIALINKS` is asked about.
+pub enum PubMediaQuery<'a> {
+    Publication {
+        symbol: &'a str,
+        issue: Option<i32>,
+    },
+    /// A single document, by MEPS document ID.
+    Document(i32),
+}
+
+// Files grouped by language code, then by file format
+#[derive(Deserialize)]
+struct PubMediaLinks {
+    files: HashMap<String, HashMap<String, Vec<PubMediaFile>>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct PublicationDownloadProgress {
+    pub publication_id: i32,
+    pub downloaded: u64,
+    pub total: u64,
+}
+
+/// Newer version of an installed publication found on the remote catalog.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PublicationUpdate {
+    pub installed_id: i32,
+    pub installed_symbol: String,
+    pub installed_timestamp: String,
+    pub language_code: Option<String>,
+
+    pub remote: RemotePublication,
+
+    // Bytes to download and growth of the installed publication
+    pub download_size: Option<i64>,
+    pub size_delta: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicationUpdateResult {
+    pub publication_id: i32,
+    pub symbol: String,
+    pub error: Option<String>,
+}
+
+/// Filters applied when browsing the remote catalog.
+#[derive(Serialize, Deserialize, Default)]
+pub struct RemoteFilter {
+    pub meps_language_id: i32,
+    pub publication_type: Option<String>,
+    pub year: Option<i32>,
+}
+
+/// Local copy of the JW Library publication catalog.
+///
+/// The catalog database is downloaded compressed (`{uuid}/catalog.db.gz`)
+/// and kept uncompressed at `{cache}/{uuid}.db` until the manifest points
+/// to a newer build.
+pub struct RemoteCatalog {
+    cache_path: PathBuf,
+    base_url: Mutex<String>,
+    pub_media_base_url: Mutex<String>,
+
+    manifest: RwLock<Option<RemoteManifest>>,
+    db: RwLock<Option<Arc<ConnectionPool>>>,
+    // Serializes refreshes, so a catalog build is downloaded only once
+    refreshing: Mutex<()>,
+    downloader: Arc<DownloadManager>,
+    // Download jobs in progress, by publication ID
+    downloads: Mutex<HashMap<i32, JobId>>,
+}
+
+impl RemoteCatalog {
+    pub fn init<T: Into<PathBuf>>(
+        location: T,
+        base_url: &str,
+        pub_media_base_url: &str,
+        downloader: Arc<DownloadManager>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let cache_path: PathBuf = location.into();
+        if !cache_path.exists() {
+            fs::create_dir_all(&cache_path)?;
+        }
+
+        let remote = Self {
+            cache_path,
+            base_url: Mutex::new(base_url.trim_end_matches('/').to_owned()),
+            pub_media_base_url: Mutex::new(pub_media_base_url.trim_end_matches('/').to_owned()),
+            manifest: RwLock::new(None),
+            db: RwLock::new(None),
+            refreshing: Mutex::new(()),
+            downloader,
+            downloads: Mutex::new(HashMap::new()),
+        };
+
+        // Reuses the last downloaded build until the next refresh
+        if let Ok(manifest_file) = fs::File::open(remote.cache_path.join("manifest.json")) {
+            match serde_json::from_reader::<_, RemoteManifest>(manifest_file) {
+                Ok(manifest) if remote.database_path(&manifest).exists() => {
+                    debug!(target: TARGET, "Using cached remote catalog {}.", manifest.current.green());
+                    remote.load(manifest);
+                }
+                _ => warn!(target: TARGET, "Cached remote catalog is incomplete, ignoring it."),
+            }
+        }
+
+        Ok(remote)
+    }
+
+    pub fn base_url(&self) -> String {
+        self.base_url
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Changes the server the catalog is fetched from, e.g. a local mock
+    /// server. Takes effect on the next refresh.
+    pub fn set_base_url(&self, base_url: &str) {
+        info!(target: TARGET, "Remote catalog base URL set to {}.", base_url.green());
+        *self.base_url.lock().unwrap_or_else(PoisonError::into_inner) =
+            base_url.trim_end_matches('/').to_owned();
+    }
+
+    pub fn pub_media_base_url(&self) -> String {
+        self.pub_media_base_url
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Changes the server publication files are resolved on.
+    pub fn set_pub_media_base_url(&self, base_url: &str) {
+        info!(target: TARGET, "Pub-media base URL set to {}.", base_url.green());
+        *self
+            .pub_media_base_url
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = base_url.trim_end_matches('/').to_owned();
+    }
+
+    pub fn manifest(&self) -> Option<RemoteManifest> {
+        self.manifest
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn database_path(&self, manifest: &RemoteManifest) -> PathBuf {
+        self.cache_path.join(format!("{}.db", manifest.current))
+    }
+
+    fn load(&self, manifest: RemoteManifest) {
+        let pool = ConnectionPool::read_only(self.database_path(&manifest), MAX_IDLE_READERS);
+        *self.db.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(pool));
+        *self
+            .manifest
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(manifest);
+    }
+
+    fn pool(&self) -> Result<Arc<ConnectionPool>, Box<dyn std::error::Error>> {
+        Ok(self
+            .db
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .ok_or("Remote catalog wasn't downloaded yet")?)
+    }
+
+    pub fn fetch_manifest(&self) -> Result<RemoteManifest, Box<dyn std::error::Error>> {
+        let url = format!("{}/{}/manifest.json", self.base_url(), CATALOG_PATH);
+        debug!(target: TARGET, "Fetching catalog manifest from {}...", url.green());
+
+        let response = http_client()?.get(&url).send()?.error_for_status()?;
+        Ok(serde_json::from_reader(response)?)
+    }
+
+    /// Downloads the current catalog build, unless it's already cached.
+    /// Returns the manifest of the catalog in use, which is the cached one
+    /// when the manifest can't be fetched.
+    pub fn refresh(&self) -> Result<RemoteManifest, Box<dyn std::error::Error>> {
+        let _refreshing = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let manifest = match self.fetch_manifest() {
+            Ok(manifest) => manifest,
+            Err(err) => match self.manifest() {
+                Some(current) => {
+                    warn!(target: TARGET, "Couldn't fetch catalog manifest, using cached catalog {}: {}", current.current, err);
+                    return Ok(current);
+                }
+                None => return Err(err),
+            },
+        };
+        if let Some(current) = self.manifest() {
+            if current.current == manifest.current {
+                debug!(target: TARGET, "Remote catalog {} is up to date.", manifest.current.green());
+                return Ok(current);
+            }
+        }
+
+        let url = format!(
+            "{}/{}/{}/catalog.db.gz",
+            self.base_url(),
+            CATALOG_PATH,
+            manifest.current
+        );
+        info!(target: TARGET, "Downloading remote catalog from {}...", url.green());
+
+        let database_path = self.database_path(&manifest);
+        let compressed_path = database_path.with_extension("db.gz");
+        self.downloader.download(
+            DownloadRequest {
+                kind: DownloadKind::Catalog,
+                label: format!("catalog {}", manifest.current),
+                url,
+                destination: compressed_path.clone(),
+                expected: ExpectedFile::default(),
+            },
+            |_| {},
+        )?;
+
+        debug!(target: TARGET, "Decompressing remote catalog...");
+        let partial_path = database_path.with_extension("db.part");
+        let decompressed = fs::File::open(&compressed_path).and_then(|compressed_file| {
+            let mut decoder = GzDecoder::new(io::BufReader::new(compressed_file));
+            let mut partial_file = fs::File::create(&partial_path)?;
+            io::copy(&mut decoder, &mut partial_file)
+        });
+        let _ = fs::remove_file(&compressed_path);
+        if let Err(err) = decompressed {
+            let _ = fs::remove_file(&partial_path);
+            return Err(err.into());
+        }
+        fs::rename(&partial_path, &database_path)?;
+
+        let manifest_file = fs::File::create(self.cache_path.join("manifest.json"))?;
+        serde_json::to_writer_pretty(manifest_file, &manifest)?;
+
+        let previous = self.manifest();
+        self.load(manifest.clone());
+
+        // Old builds may still be open by readers on some platforms
+        if let Some(previous) = previous {
+            if let Err(err) = fs::remove_file(self.database_path(&previous)) {
+                warn!(target: TARGET, "Couldn't remove old remote catalog {}: {}", previous.current, err);
+            }
+        }
+
+        info!(target: TARGET, "Remote catalog {} ready.", manifest.current.green());
+        Ok(manifest)
+    }
+
+    /// Refreshes the catalog only when no build was downloaded yet.
+    pub fn ensure_downloaded(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.manifest().is_none() {
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    pub fn get_languages(&self) -> Result<Vec<RemoteLanguage>, Box<dyn std::error::Error>> {
+        let pool = self.pool()?;
+        let db = pool.get()?;
+        let mut stmt = db.prepare(
+            "SELECT MepsLanguageId, COUNT(*) FROM Publication
+            GROUP BY MepsLanguageId
+            ORDER BY MepsLanguageId",
+        )?;
+
+        let languages = stmt
+            .query_map([], |row| {
+                Ok(RemoteLanguage {
+                    meps_language_id: row.get(0)?,
+                    publication_count: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(languages)
+    }
+
+    pub fn get_publication_types(
+        &self,
+        meps_language_id: i32,
+    ) -> Result<Vec<RemotePublicationType>, Box<dyn std::error::Error>> {
+        let pool = self.pool()?;
+        let db = pool.get()?;
+        let mut stmt = db.prepare(
+            "SELECT PublicationType.Name, COUNT(*) FROM Publication
+            INNER JOIN PublicationType ON PublicationType.Id = Publication.PublicationTypeId
+            WHERE Publication.MepsLanguageId = ?1
+            GROUP BY PublicationType.Name
+            ORDER BY PublicationType.Name",
+        )?;
+
+        let publication_types = stmt
+            .query_map([meps_language_id], |row| {
+                Ok(RemotePublicationType {
+                    name: row.get(0)?,
+                    publication_count: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(publication_types)
+    }
+
+    pub fn get_years(
+        &self,
+        meps_language_id: i32,
+        publication_type: Option<&str>,
+    ) -> Result<Vec<i32>, Box<dyn std::error::Error>> {
+        let pool = self.pool()?;
+        let db = pool.get()?;
+        let mut stmt = db.prepare(
+            "SELECT DISTINCT Publication.Year FROM Publication
+            INNER JOIN PublicationType ON PublicationType.Id = Publication.PublicationTypeId
+            WHERE Publication.MepsLanguageId = ?1 AND
+                (?2 IS NULL OR PublicationType.Name = ?2) AND
+                Publication.Year IS NOT NULL
+            ORDER BY Publication.Year DESC",
+        )?;
+
+        let years = stmt
+            .query_map(params![meps_language_id, publication_type], |row| {
+                row.get(0)
+            })?
+            .collect::<rusqlite::Result<Vec<i32>>>()?;
+
+        Ok(years)
+    }
+
+    /// Lists publications matching `filter`, marking the ones found in
+    /// `installed`.
+    pub fn get_publications(
+        &self,
+        filter: &RemoteFilter,
+        installed: &[CollectionPublication],
+    ) -> Result<Vec<RemotePublication>, Box<dyn std::error::Error>> {
+        let pool = self.pool()?;
+        let db = pool.get()?;
+        let mut stmt = db.prepare(&format!(
+            "{}
+            WHERE Publication.MepsLanguageId = ?1 AND
+                (?2 IS NULL OR PublicationType.Name = ?2) AND
+                (?3 IS NULL OR Publication.Year = ?3)
+            ORDER BY Publication.Year DESC, Publication.IssueTagNumber DESC, Publication.Title",
+            PUBLICATION_QUERY
+        ))?;
+
+        let mut publications = stmt
+            .query_map(
+                params![
+                    filter.meps_language_id,
+                    filter.publication_type,
+                    filter.year
+                ],
+                RemotePublication::from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        for publication in publications.iter_mut() {
+            publication.installed = installed
+                .iter()
+                .any(|installed| publication.matches(installed));
+        }
+
+        debug!(
+            target: TARGET,
+            "Browsing remote catalog (Lang: {}; Type: {}; Year: {})... (Length: {} items)",
+            filter.meps_language_id.to_string().yellow(),
+            filter.publication_type.as_deref().unwrap_or("*").green(),
+            filter.year.map(|year| year.to_string()).unwrap_or("*".to_owned()).yellow(),
+            publications.len().to_string().yellow()
+        );
+
+        Ok(publications)
+    }
+
+    pub fn get_publication(
+        &self,
+        publication_id: i32,
+    ) -> Result<Option<RemotePublication>, Box<dyn std::error::Error>> {
+        let pool = self.pool()?;
+        let db = pool.get()?;
+        let mut stmt = db.prepare(&format!("{} WHERE Publication.Id = ?1", PUBLICATION_QUERY))?;
+
+        let mut rows = stmt.query([publication_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(RemotePublication::from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolves the files of a publication in `file_format` (e.g. `JWPUB`,
+    /// `MP3`) through the `GETPUBMEDIALINKS` API.
+    pub fn get_pub_media_files(
+        &self,
+        symbol: &str,
+        issue: Option<i32>,
+        language_code: &str,
+        file_format: &str,
+    ) -> Result<Vec<PubMediaFile>, Box<dyn std::error::Error>> {
+        Ok(self
+            .get_pub_media_links(
+                &PubMediaQuery::Publication { symbol, issue },
+                language_code,
+                &[file_format],
+            )?
+            .remove(file_format)
+            .unwrap_or_default())
+    }
+
+    /// Resolves the files of a publication or document in every format of
+    /// `file_formats`, grouped by format.
+    pub fn get_pub_media_links(
+        &self,
+        query: &PubMediaQuery,
+        language_code: &str,
+        file_formats: &[&str],
+    ) -> Result<HashMap<String, Vec<PubMediaFile>>, Box<dyn std::error::Error>> {
+        let mut url = reqwest::Url::parse(&format!(
+            "{}/apis/pub-media/GETPUBMEDIALINKS",
+            self.pub_media_base_url()
+        ))?;
+        url.query_pairs_mut()
+            .append_pair("output", "json")
+            .append_pair("alllangs", "0")
+            .append_pair("langwritten", language_code)
+            .append_pair("fileformat", &file_formats.join(","));
+        match query {
+            PubMediaQuery::Publication { symbol, issue } => {
+                url.query_pairs_mut().append_pair("pub", symbol);
+                if let Some(issue) = issue {
+                    url.query_pairs_mut()
+                        .append_pair("issue", &issue.to_string());
+                }
+            }
+            PubMediaQuery::Document(meps_document_id) => {
+                url.query_pairs_mut()
+                    .append_pair("docid", &meps_document_id.to_string());
+            }
+        }
+
+        debug!(target: TARGET, "Resolving media links at {}...", url.as_str().green());
+        let response = http_client()?.get(url).send()?;
+        // Unknown publications and documents are answered with 404
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(HashMap::new());
+        }
+        let mut links: PubMediaLinks = serde_json::from_reader(response.error_for_status()?)?;
+
+        Ok(links.files.remove(language_code).unwrap_or_default())
+    }
+
+    /// Downloads the latest JWPUB of a remote publication and installs it
+    /// on `catalog`.
+    ///
+    /// Interrupted downloads are resumed on the next call for the same
+    /// publication, until the file is complete and verified.
+    pub fn download_publication(
+        &self,
+        catalog: &Catalog,
+        publication_id: i32,
+        language_code: &str,
+        mut on_progress: impl FnMut(PublicationDownloadProgress),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (mut publication, mut file) = self.resolve_jwpub(publication_id, language_code)?;
+
+        // A different file than the catalog describes is likely a newer
+        // revision the cached catalog build doesn't know about yet
+        if !publication.describes(&file) {
+            info!(target: TARGET, "Catalog doesn't describe the JWPUB of \"{}\", refreshing it...", publication.symbol.bright_magenta());
+            self.refresh()?;
+            (publication, file) = self.resolve_jwpub(publication_id, language_code)?;
+            if !publication.describes(&file) {
+                return Err(format!(
+                    "JWPUB of \"{}\" is {} bytes, the catalog expects {}",
+                    publication.symbol,
+                    file.filesize,
+                    publication.size.unwrap_or_default()
+                )
+                .into());
+            }
+        }
+
+        // Named after the publication so an interrupted download is resumed
+        let destination = self
+            .cache_path
+            .join("downloads")
+            .join(format!("{}.jwpub", publication_id));
+
+        info!(target: TARGET, "Downloading \"{}\" from {}...", publication.symbol.bright_magenta(), file.file.url.green());
+        let ticket = {
+            let mut downloads = self
+                .downloads
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if downloads.contains_key(&publication_id) {
+                return Err(
+                    format!("\"{}\" is already being downloaded", publication.symbol).into(),
+                );
+            }
+
+            let ticket = self.downloader.enqueue(vec![DownloadRequest {
+                kind: DownloadKind::Publication,
+                label: publication.symbol.clone(),
+                url: file.file.url.clone(),
+                destination: destination.clone(),
+                expected: ExpectedFile {
+                    size: Some(file.filesize),
+                    md5: file.file.checksum.clone(),
+                },
+            }]);
+            downloads.insert(publication_id, ticket.job_ids[0]);
+            ticket
+        };
+
+        let result = ticket.wait(|event| {
+            on_progress(PublicationDownloadProgress {
+                publication_id,
+                downloaded: event.downloaded,
+                total: event.total,
+            })
+        });
+
+        self.downloads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&publication_id);
+        result?;
+
+        let installed = catalog.install_jwpub_file(&destination);
+        if let Err(err) = fs::remove_file(&destination) {
+            warn!(target: TARGET, "Couldn't remove downloaded file {}: {}", destination.display(), err);
+        }
+
+        installed
+    }
+
+    // Latest JWPUB of a remote publication on the pub-media API
+    fn resolve_jwpub(
+        &self,
+        publication_id: i32,
+        language_code: &str,
+    ) -> Result<(RemotePublication, PubMediaFile), Box<dyn std::error::Error>> {
+        let publication = self
+            .get_publication(publication_id)?
+            .ok_or("Publication not found in remote catalog")?;
+
+        let (symbol, issue) = publication.pub_media_query();
+        let file = self
+            .get_pub_media_files(symbol, issue, language_code, "JWPUB")?
+            .into_iter()
+            .next()
+            .ok_or(format!(
+                "No JWPUB available for \"{}\" in language {}",
+                publication.symbol, language_code
+            ))?;
+
+        Ok((publication, file))
+    }
+
+    /// Cancels an in progress download. Returns `false` when the
+    /// publication wasn't being downloaded.
+    pub fn cancel_download(&self, publication_id: i32) -> bool {
+        match self
+            .downloads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&publication_id)
+        {
+            Some(job_id) => {
+                info!(target: TARGET, "Cancelling download of publication ID {}...", publication_id);
+                self.downloader.cancel(*job_id)
+            }
+            None => false,
+        }
+    }
+
+    /// Compares every installed publication with the cached catalog,
+    /// listing the ones whose latest asset is a different, newer package.
+    pub fn get_available_updates(
+        &self,
+        installed: &[CollectionPublication],
+    ) -> Result<Vec<PublicationUpdate>, Box<dyn std::error::Error>> {
+        let pool = self.pool()?;
+        let db = pool.get()?;
+        let mut stmt = db.prepare(&format!(
+            "{}
+            WHERE Publication.MepsLanguageId = ?1 AND
+                IFNULL(Publication.IssueTagNumber, 0) = ?2 AND
+                (Publication.Symbol = ?3 OR (?2 != 0 AND Publication.KeySymbol = ?4))",
+            PUBLICATION_QUERY
+        ))?;
+
+        let mut updates = Vec::new();
+        for publication in installed {
+            let remote = stmt
+                .query_map(
+                    params![
+                        publication.language_index,
+                        publication.issue_tag_number.unwrap_or(0),
+                        publication.symbol,
+                        publication.key_symbol
+                    ],
+                    RemotePublication::from_row,
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+                .into_iter()
+                .find(|remote| remote.matches(publication));
+
+            let Some(mut remote) = remote else {
+                continue;
+            };
+
+            if is_update(&remote, &publication.hash, &publication.timestamp) {
+                remote.installed = true;
+                updates.push(PublicationUpdate {
+                    installed_id: publication.id,
+                    installed_symbol: publication.jwpub.replace(".jwpub", ""),
+                    installed_timestamp: publication.timestamp.clone(),
+                    language_code: publication.language_code().map(str::to_owned),
+                    download_size: remote.size,
+                    size_delta: remote
+                        .expanded_size
+                        .map(|size| size - publication.expanded_size as i64),
+                    remote,
+                });
+            }
+        }
+
+        debug!(target: TARGET, "Checking updates for {} installed publications... (Length: {} items)", installed.len().to_string().yellow(), updates.len().to_string().yellow());
+
+        Ok(updates)
+    }
+
+    /// Downloads and installs every update in `updates`, one after the
+    /// other. A failed update doesn't stop the remaining ones.
+    pub fn apply_updates(
+        &self,
+        catalog: &Catalog,
+        updates: &[PublicationUpdate],
+        mut on_progress: impl FnMut(PublicationDownloadProgress),
+    ) -> Vec<PublicationUpdateResult> {
+        updates
+            .iter()
+            .map(|update| {
+                let result = match &update.language_code {
+                    Some(language_code) => self.download_publication(
+                        catalog,
+                        update.remote.id,
+                        language_code,
+                        &mut on_progress,
+                    ),
+                    None => Err("Couldn't find the publication language code".into()),
+                };
+
+                if let Err(err) = &result {
+                    error!(target: TARGET, "Couldn't update \"{}\": {}", update.installed_symbol, err);
+                }
+
+                PublicationUpdateResult {
+                    publication_id: update.remote.id,
+                    symbol: update.installed_symbol.clone(),
+                    error: result.err().map(|err| err.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+// The asset signature identifies the package by its manifest hash, which
+// may be followed by other `:` separated parts. Catalog timestamps follow
+// the catalog build, not the package, so they only tell an older package
+// apart from a newer one.
+fn is_update(remote: &RemotePublication, installed_hash: &str, installed_timestamp: &str) -> bool {
+    let Some(signature) = remote.signature.as_deref() else {
+        return false;
+    };
+    if signature
+        .split(':')
+        .any(|part| part.eq_ignore_ascii_case(installed_hash))
+    {
+        return false;
+    }
+
+    match (
+        remote.last_modified.as_deref().and_then(parse_timestamp),
+        parse_timestamp(installed_timestamp),
+    ) {
+        (Some(remote_timestamp), Some(installed_timestamp)) => {
+            remote_timestamp > installed_timestamp
+        }
+        _ => false,
+    }
+}
+
+// Catalog and manifest timestamps don't share a single format
+fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%SZ")
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|timestamp| timestamp.naive_utc())
+        })
+        .or_else(|| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").ok())
+}
+
+pub fn http_client() -> reqwest::Result<reqwest::blocking::Client> {
+    reqwest::blocking::Client::builder()
+        .user_agent(concat!("open-witness-library/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use rusqlite::Connection;
+
+    use super::*;
+    use crate::publib::test_server::{TestResponse, TestServer};
+
+    const MANIFEST_PATH: &'static str = "/catalogs/publications/v4/manifest.json";
+
+    // Compressed catalog build with two English publications and one in
+    // Portuguese, the latest asset of `bhs` being `bhs_size` bytes
+    fn catalog_build(bhs_size: i64) -> Vec<u8> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.db");
+        let db = Connection::open(&path).unwrap();
+        db.execute_batch(
+            "CREATE TABLE PublicationType (Id INTEGER, Name TEXT);
+            CREATE TABLE Publication (
+                Id INTEGER, MepsLanguageId INTEGER, PublicationTypeId INTEGER,
+                IssueTagNumber INTEGER, Title TEXT, ShortTitle TEXT, IssueTitle TEXT,
+                CoverTitle TEXT, UndatedTitle TEXT, Year INTEGER, Symbol TEXT, KeySymbol TEXT
+            );
+            CREATE TABLE PublicationAsset (
+                Id INTEGER, PublicationId INTEGER, Size INTEGER, ExpandedSize INTEGER,
+                Signature TEXT, LastModified TEXT
+            );
+            INSERT INTO PublicationType VALUES (1, 'Book'), (2, 'Watchtower');
+            INSERT INTO Publication VALUES
+                (10, 0, 1, 0, 'Bible Teach', NULL, NULL, NULL, NULL, 2015, 'bhs', NULL),
+                (11, 0, 2, 20241100, 'Watchtower', NULL, NULL, NULL, NULL, 2024, 'w24', 'w'),
+                (12, 5, 1, 0, 'Bíblia Ensina', NULL, NULL, NULL, NULL, 2015, 'bhs', NULL);
+            INSERT INTO PublicationAsset VALUES
+                (1, 10, 100, 200, 'a', '2024-01-01T00:00:00Z'),
+                (2, 10, 110, 210, 'b', '2024-06-01T00:00:00Z');",
+        )
+        .unwrap();
+        db.execute(
+            "UPDATE PublicationAsset SET Size = ?1 WHERE Id = 2",
+            [bhs_size],
+        )
+        .unwrap();
+        drop(db);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&fs::read(&path).unwrap()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn catalog_server(current: &'static str, build: Vec<u8>) -> TestServer {
+        TestServer::start(move |request| {
+            let build_path = format!("/catalogs/publications/v4/{}/catalog.db.gz", current);
+            if request.path == MANIFEST_PATH {
+                TestResponse::ok(format!(r#"{{"version": 1, "current": "{}"}}"#, current))
+            } else if request.path == build_path {
+                TestResponse::ok(build.clone())
+            } else {
+                TestResponse::status(404)
+            }
+        })
+    }
+
+    fn remote_catalog(location: &std::path::Path, base_url: &str) -> RemoteCatalog {
+        RemoteCatalog::init(
+            location,
+            base_url,
+            DEFAULT_PUB_MEDIA_BASE_URL,
+            Arc::new(DownloadManager::new(1, |_| {})),
+        )
+        .unwrap()
+    }
+
+    fn cached_files(location: &std::path::Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(location)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn refresh_downloads_and_caches_build() {
+        let server = catalog_server("build-1", catalog_build(110));
+        let cache = tempfile::tempdir().unwrap();
+        let remote = remote_catalog(cache.path(), &server.base_url);
+        assert!(remote.manifest().is_none());
+
+        let manifest = remote.refresh().unwrap();
+        assert_eq!(manifest.version, 1);
+        assert_eq!(manifest.current, "build-1");
+
+        // Only the decompressed build is left, renamed from its partial file
+        assert_eq!(cached_files(cache.path()), ["build-1.db", "manifest.json"]);
+
+        let languages = remote.get_languages().unwrap();
+        assert_eq!(languages.len(), 2);
+        assert_eq!(languages[0].meps_language_id, 0);
+        assert_eq!(languages[0].publication_count, 2);
+
+        let books = remote
+            .get_publications(
+                &RemoteFilter {
+                    meps_language_id: 0,
+                    publication_type: Some("Book".to_owned()),
+                    year: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].symbol, "bhs");
+        // Latest asset
+        assert_eq!(books[0].size, Some(110));
+
+        // Same build, nothing is downloaded again
+        remote.refresh().unwrap();
+        assert_eq!(server.hits(MANIFEST_PATH), 2);
+        assert_eq!(
+            server.hits("/catalogs/publications/v4/build-1/catalog.db.gz"),
+            1
+        );
+    }
+
+    #[test]
+    fn cached_build_is_used_offline() {
+        let cache = tempfile::tempdir().unwrap();
+        let offline_url = {
+            let server = catalog_server("build-1", catalog_build(110));
+            remote_catalog(cache.path(), &server.base_url)
+                .refresh()
+                .unwrap();
+            server.base_url.clone()
+        };
+
+        let remote = remote_catalog(cache.path(), &offline_url);
+        assert_eq!(remote.manifest().unwrap().current, "build-1");
+        remote.ensure_downloaded().unwrap();
+        assert_eq!(remote.refresh().unwrap().current, "build-1");
+        assert_eq!(remote.get_languages().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn new_build_replaces_cached_one() {
+        let cache = tempfile::tempdir().unwrap();
+        {
+            let server = catalog_server("build-1", catalog_build(110));
+            remote_catalog(cache.path(), &server.base_url)
+                .refresh()
+                .unwrap();
+        }
+
+        let server = catalog_server("build-2", catalog_build(110));
+        let remote = remote_catalog(cache.path(), &server.base_url);
+        assert_eq!(remote.refresh().unwrap().current, "build-2");
+        assert_eq!(cached_files(cache.path()), ["build-2.db", "manifest.json"]);
+    }
+
+    // Serves the JWPUB of `bhs` as 120 bytes, while the catalog build
+    // lists 110 bytes until `current` is switched to `build-2`
+    fn revised_jwpub_server(current: Arc<Mutex<&'static str>>) -> TestServer {
+        let builds = [
+            ("build-1", catalog_build(110)),
+            ("build-2", catalog_build(120)),
+        ];
+        TestServer::start(move |request| {
+            let current = *current.lock().unwrap();
+            if request.path == MANIFEST_PATH {
+                return TestResponse::ok(format!(r#"{{"version": 1, "current": "{}"}}"#, current));
+            }
+            if request
+                .path
+                .starts_with("/apis/pub-media/GETPUBMEDIALINKS?")
+            {
+                return TestResponse::ok(format!(
+                    r#"{{"files": {{"E": {{"JWPUB": [{{
+                        "file": {{"url": "http://{}/bhs_E.jwpub", "checksum": null}},
+                        "filesize": 120
+                    }}]}}}}}}"#,
+                    request.headers["host"]
+                ));
+            }
+            if request.path == "/bhs_E.jwpub" {
+                return TestResponse::ok(vec![0; 120]);
+            }
+            for (build, data) in &builds {
+                if request.path == format!("/catalogs/publications/v4/{}/catalog.db.gz", build) {
+                    return TestResponse::ok(data.clone());
+                }
+            }
+            TestResponse::status(404)
+        })
+    }
+
+    #[test]
+    fn size_mismatch_refreshes_catalog() {
+        let current = Arc::new(Mutex::new("build-1"));
+        let server = revised_jwpub_server(current.clone());
+        let cache = tempfile::tempdir().unwrap();
+        let remote = RemoteCatalog::init(
+            cache.path(),
+            &server.base_url,
+            &server.base_url,
+            Arc::new(DownloadManager::new(1, |_| {})),
+        )
+        .unwrap();
+        remote.refresh().unwrap();
+        let catalog = Catalog::init(cache.path().join("catalog")).unwrap();
+
+        // Still the stale build after refreshing
+        let err = remote
+            .download_publication(&catalog, 10, "E", |_| {})
+            .unwrap_err();
+        assert!(err.to_string().contains("the catalog expects 110"));
+        assert_eq!(server.hits(MANIFEST_PATH), 2);
+        assert!(!cache
+            .path()
+            .join("downloads")
+            .join("10.jwpub.part")
+            .exists());
+
+        // The new build lists the revised JWPUB, which is then downloaded
+        // and fails to install
+        *current.lock().unwrap() = "build-2";
+        let err = remote
+            .download_publication(&catalog, 10, "E", |_| {})
+            .unwrap_err();
+        assert!(!err.to_string().contains("the catalog expects"));
+        assert_eq!(server.hits("/bhs_E.jwpub"), 1);
+        assert_eq!(remote.manifest().unwrap().current, "build-2");
+        assert_eq!(remote.get_publication(10).unwrap().unwrap().size, Some(120));
+    }
+
+    #[test]
+    fn updates_compare_package_hashes() {
+        let cache = tempfile::tempdir().unwrap();
+        let server = catalog_server("build-1", catalog_build(110));
+        let remote = remote_catalog(cache.path(), &server.base_url);
+        remote.refresh().unwrap();
+        // Asset signed "b", modified on 2024-06-01
+        let bhs = remote.get_publication(10).unwrap().unwrap();
+
+        // Same package, cataloged after it was built
+        assert!(!is_update(&bhs, "b", "2024-03-01T00:00:00Z"));
+        assert!(!is_update(&bhs, "B", "2024-03-01T00:00:00Z"));
+        // Different package
+        assert!(is_update(&bhs, "a", "2024-03-01T00:00:00Z"));
+        // Different but older package
+        assert!(!is_update(&bhs, "c", "2024-07-01T00:00:00Z"));
+
+        let mut signed = bhs.clone();
+        signed.signature = Some("b:extra".to_owned());
+        assert!(!is_update(&signed, "b", "2024-03-01T00:00:00Z"));
+        signed.signature = None;
+        assert!(!is_update(&signed, "a", "2024-03-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn corrupt_build_is_not_kept() {
+        let server = catalog_server("build-1", b"not gzip".to_vec());
+        let cache = tempfile::tempdir().unwrap();
+        let remote = remote_catalog(cache.path(), &server.base_url);
+
+        assert!(remote.refresh().is_err());
+        assert!(remote.manifest().is_none());
+        assert!(cached_files(cache.path()).is_empty());
+    }
+}
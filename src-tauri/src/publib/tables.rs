@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 pub struct DatedText {
@@ -13,6 +14,9 @@ pub struct DatedText {
 
     pub first_date_offset: i32,
     pub last_date_offset: i32,
+    // Typed views of the date offsets above
+    pub first_date: Option<NaiveDate>,
+    pub last_date: Option<NaiveDate>,
 
     // Foreign keys to `Footnote` table
     pub first_footnote_id: Option<i32>,
@@ -628,14 +632,15 @@ pub struct Question {
     target_paragraph_number_label: i32,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct RelatedDocument {
-    id: i32,
+    pub id: i32,
 
     // Foreign key to `Document` table
-    document_id: i32,
-    meps_document_id: i32,
+    pub document_id: i32,
+    pub meps_document_id: i32,
 
-    relationship_type: i32,
+    pub relationship_type: i32,
 }
 
 pub struct SearchIndexBibleVerse {
@@ -689,22 +694,24 @@ pub struct TextUnit {
     unit_id: i32,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Topic {
-    id: i32,
+    pub id: i32,
 
-    topic: String,
-    display_topic: String,
-    display_topic_rich: Option<String>,
+    pub topic: String,
+    pub display_topic: String,
+    pub display_topic_rich: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct TopicDocument {
-    id: i32,
+    pub id: i32,
 
     // Foreign key to `Document` table
-    document_id: i32,
+    pub document_id: i32,
 
     // Foreign key to `Topic` table
-    topic_id: i32,
+    pub topic_id: i32,
 }
 
 pub struct VerseCommentary {
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+};
+
+/// Request received by a `TestServer`. Header names are lowercase.
+#[derive(Clone, Debug)]
+pub struct TestRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+pub struct TestResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    pub fn ok<T: Into<Vec<u8>>>(body: T) -> Self {
+        Self {
+            status: 200,
+            body: body.into(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            body: Vec::new(),
+        }
+    }
+}
+
+/// HTTP server on a random localhost port, standing in for the remote
+/// APIs whose base URL can be changed. Requests are answered one at a
+/// time by `handler` and recorded.
+pub struct TestServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<TestRequest>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl TestServer {
+    pub fn start(handler: impl Fn(&TestRequest) -> TestResponse + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Couldn't bind test server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        {
+            let requests = requests.clone();
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    if let Some(request) = read_request(&stream) {
+                        let response = handler(&request);
+                        requests
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .push(request);
+                        let _ = write_response(stream, response);
+                    }
+                }
+            });
+        }
+
+        Self {
+            base_url,
+            requests,
+            stopped,
+        }
+    }
+
+    pub fn requests(&self) -> Vec<TestRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// How many requests were made to `path`, query excluded.
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests()
+            .iter()
+            .filter(|request| request.path.split('?').next() == Some(path))
+            .count()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wakes up the listener so it sees it was stopped
+        let _ = TcpStream::connect(self.base_url.trim_start_matches("http://"));
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Option<TestRequest> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_owned();
+    let path = parts.next()?.to_owned();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_owned());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(TestRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn write_response(mut stream: TcpStream, response: TestResponse) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
@@ -29,6 +29,7 @@ const NextDocumentButton = () => {
 
 const Document = (params) => {
     const { documentId, currentId } = params;
+    const { symbol } = useParams();
     const [content, setContent] = useState("");
     const contentRef = useRef(null);
     const swiper = useSwiper();
@@ -37,10 +38,10 @@ const Document = (params) => {
 
     useEffect(() => {
         const fetchData = async () => {
-            setContent(await invoke("catalog_get_document_content", {documentId: documentId}));
+            setContent(await invoke("catalog_get_document_content_from", {filenameSymbol: symbol, documentId: documentId}));
         };
         fetchData();
-    }, [documentId]);
+    }, [symbol, documentId]);
 
     useEffect(() => {
         const updateImageSources = async (contentHtml) => {
@@ -53,7 +54,7 @@ const Document = (params) => {
                 if (src && src.startsWith('jwpub-media://')) {
                     const imageName = src.split('jwpub-media://')[1];
                     // Convertendo o caminho do arquivo usando convertFileSrc
-                    const newSrc = convertFileSrc(imageName, 'jwpub-media');
+                    const newSrc = convertFileSrc(`${symbol}/${imageName}`, 'jwpub-media');
                     img.setAttribute('src', newSrc);  // Atualiza o src da imagem
                 }
             }
@@ -72,7 +73,7 @@ const Document = (params) => {
                 
                 try {
                     // Converte o caminho usando `convertFileSrc`
-                    const newSrc = convertFileSrc(imgPath, 'jwpub-media');
+                    const newSrc = convertFileSrc(`${symbol}/${imgPath}`, 'jwpub-media');
         
                     // Define o estilo `background-image` com a URL convertida
                     element.style.backgroundImage = `url('${newSrc}')`;
@@ -93,7 +94,7 @@ const Document = (params) => {
         };
 
         updateContent();
-    }, [content]);
+    }, [content, symbol]);
 
     return (
         <div
@@ -133,8 +134,7 @@ const DocumentView = () => {
 
     useEffect(() => {
         const fetchData = async () => {
-            await invoke("catalog_open_connection", {filenameSymbol: symbol});
-            setDocuments(await invoke("catalog_get_documents"));
+            setDocuments(await invoke("catalog_get_documents_from", {filenameSymbol: symbol}));
         };
         fetchData();
         setId(parseInt(documentId));
@@ -53,7 +53,6 @@ const PublicationView = () => {
 
     useEffect(() => {
         const fetchViewItems = async () => {
-            await invoke("catalog_open_connection", {filenameSymbol: symbol});
             const viewItem = await invoke("catalog_get_publication_view_from", {filenameSymbol: symbol});
             const documents = await fetchDocuments(viewItem.publication_view_items_documents);
             setViewItems({
@@ -67,7 +66,8 @@ const PublicationView = () => {
     const fetchDocuments = async (items) => {
         let documents = [];
         for (const item of items) {
-            const document = await invoke("catalog_get_document_by_id", {
+            const document = await invoke("catalog_get_document_by_id_from", {
+                filenameSymbol: symbol,
                 documentId: item.document_id
             });
             documents.push({ ...item, document });